@@ -0,0 +1,74 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{ext_contract, AccountId};
+
+/// The roles an account can hold, globally or in a single community.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Manages every role. Only held globally.
+    Owner,
+    /// Manages every role but the owner and the admin. Only held globally.
+    Admin,
+    Minter,
+    Pauser,
+    /// Manages the minters, pausers and community admins of its community.
+    CommunityAdmin,
+}
+
+impl Role {
+    /// The name of the role in the `role_granted` and `role_revoked` events.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Owner => "owner",
+            Self::Admin => "admin",
+            Self::Minter => "minter",
+            Self::Pauser => "pauser",
+            Self::CommunityAdmin => "community_admin",
+        }
+    }
+
+    /// Whether the role can be held in a single community.
+    pub fn is_scoped(&self) -> bool {
+        matches!(self, Self::Minter | Self::Pauser | Self::CommunityAdmin)
+    }
+}
+
+/// Roles held globally, when `contract_id` is `None`, or in the community `contract_id`.
+/// A global role holds in every community too, and a community account holds every scoped role
+/// in its own community.
+#[ext_contract(ext_access_control)]
+pub trait AccessControlCore {
+    /// Grants `role` to `account_id`. The owner can grant any role and the admin any role but
+    /// the owner and the admin. In a community, the community account and its community admins
+    /// can grant the scoped roles as well. Requires at least 1 yoctoNEAR attached, and a deposit
+    /// to cover the added storage.
+    fn acl_grant_role(&mut self, role: Role, account_id: AccountId, contract_id: Option<AccountId>);
+
+    /// Revokes `role` from `account_id`, by the same accounts that can grant it. The last owner
+    /// cannot be revoked. Requires 1 yoctoNEAR attached.
+    ///
+    /// Once an admin timelock is set, both only work for the community account and its community
+    /// admins, and the owners and admins go through the queue.
+    fn acl_revoke_role(&mut self, role: Role, account_id: AccountId, contract_id: Option<AccountId>);
+
+    /// Gives up a role of the caller, unless it is the last owner. Requires 1 yoctoNEAR attached.
+    ///
+    /// Once an admin timelock is set, the owners and admins go through a `revoke_role` of the
+    /// queue instead.
+    fn acl_renounce_role(&mut self, role: Role, contract_id: Option<AccountId>);
+
+    fn acl_has_role(&self, role: Role, account_id: AccountId, contract_id: Option<AccountId>) -> bool;
+
+    /// The accounts granted `role` exactly in `contract_id`, without the global holders when
+    /// `contract_id` is set.
+    fn acl_role_members(
+        &self,
+        role: Role,
+        contract_id: Option<AccountId>,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId>;
+}
@@ -0,0 +1,313 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, require, AccountId, IntoStorageKey};
+
+use crate::access_control::core::{AccessControlCore, Role};
+use crate::fungible_token::events::{RoleGranted, RoleRevoked};
+use crate::fungible_token::storage_impl::settle_storage_deposit;
+
+/// Implementation of the roles, kept apart from the tokens so that a contract can guard its own
+/// methods with them through [`require_role!`](crate::require_role).
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AccessControl {
+    prefix: Vec<u8>,
+
+    /// (Role, community or none for global) -> accounts holding the role.
+    pub members: LookupMap<(Role, Option<AccountId>), UnorderedSet<AccountId>>,
+
+    /// Set by [`AdminTimelock::new`](crate::access_control::AdminTimelock::new). The global
+    /// roles then only change, and pause, through the queue.
+    pub timelocked: bool,
+}
+
+impl AccessControl {
+    /// Creates the roles with `owner_id` as the first owner.
+    pub fn new<S>(prefix: S, owner_id: &AccountId) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        let mut this = Self { members: LookupMap::new([prefix.as_slice(), b"m"].concat()), prefix, timelocked: false };
+        this.internal_grant_role(Role::Owner, owner_id, None, owner_id);
+        this
+    }
+
+    fn nested_prefix(&self, role: Role, contract_id: Option<&AccountId>) -> Vec<u8> {
+        let scope = contract_id.map_or(&b""[..], |contract_id| contract_id.as_bytes());
+        [self.prefix.as_slice(), b"m:", role.as_str().as_bytes(), b":", scope].concat()
+    }
+
+    fn is_member(&self, role: Role, account_id: &AccountId, contract_id: Option<&AccountId>) -> bool {
+        match self.members.get(&(role, contract_id.cloned())) {
+            Some(members) => members.contains(account_id),
+            None => false
+        }
+    }
+
+    /// Whether `account_id` holds `role` globally, or in `contract_id` if set.
+    pub fn has_role(&self, role: Role, account_id: &AccountId, contract_id: Option<&AccountId>) -> bool {
+        if self.is_member(role, account_id, None) {
+            return true;
+        }
+        match contract_id {
+            Some(contract_id) => self.has_community_role(role, account_id, contract_id),
+            None => false
+        }
+    }
+
+    /// Whether `account_id` holds `role` in `contract_id` itself, not counting the global holders.
+    pub fn has_community_role(&self, role: Role, account_id: &AccountId, contract_id: &AccountId) -> bool {
+        role.is_scoped() && (contract_id == account_id || self.is_member(role, account_id, Some(contract_id)))
+    }
+
+    /// Panics if a timelock is set, unless `sender_id` acts with `role` of the community
+    /// `contract_id` rather than a global role.
+    pub fn assert_not_timelocked(&self, role: Role, sender_id: &AccountId, contract_id: Option<&AccountId>) {
+        if self.timelocked {
+            let is_community_role = match contract_id {
+                Some(contract_id) => self.has_community_role(role, sender_id, contract_id),
+                None => false
+            };
+            require!(is_community_role, "The change has to go through the admin timelock");
+        }
+    }
+
+    /// Panics unless `account_id` holds `role` globally, or in `contract_id` if set.
+    pub fn assert_role(&self, role: Role, account_id: &AccountId, contract_id: Option<&AccountId>) {
+        require!(self.has_role(role, account_id, contract_id), format!("The account is missing the {} role", role.as_str()));
+    }
+
+    /// Whether `sender_id` can grant and revoke `role` in `contract_id`.
+    pub fn can_manage(&self, role: Role, sender_id: &AccountId, contract_id: Option<&AccountId>) -> bool {
+        if self.is_member(Role::Owner, sender_id, None) {
+            return true;
+        }
+        if !role.is_scoped() {
+            return false;
+        }
+        self.is_member(Role::Admin, sender_id, None)
+            || (contract_id.is_some() && self.has_role(Role::CommunityAdmin, sender_id, contract_id))
+    }
+
+    /// Adds `account_id` to the holders of `role` in `contract_id` and logs `role_granted`.
+    /// Returns whether it was not a holder yet.
+    pub fn internal_grant_role(
+        &mut self,
+        role: Role,
+        account_id: &AccountId,
+        contract_id: Option<&AccountId>,
+        sender_id: &AccountId,
+    ) -> bool {
+        require!(contract_id.is_none() || role.is_scoped(), "The owner and admin roles can only be held globally");
+        let key = (role, contract_id.cloned());
+        let mut members = self
+            .members
+            .get(&key)
+            .unwrap_or_else(|| UnorderedSet::new(self.nested_prefix(role, contract_id)));
+        if !members.insert(account_id) {
+            return false;
+        }
+        self.members.insert(&key, &members);
+        RoleGranted { role: role.as_str(), account_id, sender_id, contract_id }.emit();
+        true
+    }
+
+    /// Removes `account_id` from the holders of `role` in `contract_id` and logs `role_revoked`.
+    /// Returns whether it was a holder. Panics if it is the last owner.
+    pub fn internal_revoke_role(
+        &mut self,
+        role: Role,
+        account_id: &AccountId,
+        contract_id: Option<&AccountId>,
+        sender_id: &AccountId,
+    ) -> bool {
+        let key = (role, contract_id.cloned());
+        let mut members = match self.members.get(&key) {
+            Some(members) => members,
+            None => return false
+        };
+        if !members.remove(account_id) {
+            return false;
+        }
+        require!(role != Role::Owner || !members.is_empty(), "The last owner cannot be removed");
+        if members.is_empty() {
+            self.members.remove(&key);
+        } else {
+            self.members.insert(&key, &members);
+        }
+        RoleRevoked { role: role.as_str(), account_id, sender_id, contract_id }.emit();
+        true
+    }
+}
+
+impl AccessControlCore for AccessControl {
+    fn acl_grant_role(&mut self, role: Role, account_id: AccountId, contract_id: Option<AccountId>) {
+        require!(env::attached_deposit() >= 1, "Requires attached deposit of at least 1 yoctoNEAR");
+        let initial_storage_usage = env::storage_usage();
+        let sender_id = env::predecessor_account_id();
+        require!(self.can_manage(role, &sender_id, contract_id.as_ref()), "The caller cannot manage this role");
+        self.assert_not_timelocked(Role::CommunityAdmin, &sender_id, contract_id.as_ref());
+        self.internal_grant_role(role, &account_id, contract_id.as_ref(), &sender_id);
+        settle_storage_deposit(initial_storage_usage);
+    }
+
+    fn acl_revoke_role(&mut self, role: Role, account_id: AccountId, contract_id: Option<AccountId>) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        require!(self.can_manage(role, &sender_id, contract_id.as_ref()), "The caller cannot manage this role");
+        self.assert_not_timelocked(Role::CommunityAdmin, &sender_id, contract_id.as_ref());
+        require!(self.internal_revoke_role(role, &account_id, contract_id.as_ref(), &sender_id), "The account does not hold this role");
+    }
+
+    fn acl_renounce_role(&mut self, role: Role, contract_id: Option<AccountId>) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        // An owner or admin leaving could drop the approvers of the queue below its threshold.
+        if matches!(role, Role::Owner | Role::Admin) {
+            self.assert_not_timelocked(role, &account_id, None);
+        }
+        require!(self.internal_revoke_role(role, &account_id, contract_id.as_ref(), &account_id), "The account does not hold this role");
+    }
+
+    fn acl_has_role(&self, role: Role, account_id: AccountId, contract_id: Option<AccountId>) -> bool {
+        self.has_role(role, &account_id, contract_id.as_ref())
+    }
+
+    fn acl_role_members(
+        &self,
+        role: Role,
+        contract_id: Option<AccountId>,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<AccountId> {
+        match self.members.get(&(role, contract_id)) {
+            Some(members) => members
+                .iter()
+                .skip(from_index.map_or(0, |index| index.0 as usize))
+                .take(limit.map_or(usize::MAX, |limit| limit as usize))
+                .collect(),
+            None => vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{community, set_caller};
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::ONE_NEAR;
+
+    fn setup() -> AccessControl {
+        set_caller(accounts(0), ONE_NEAR);
+        AccessControl::new(b"r".to_vec(), &accounts(0))
+    }
+
+    #[test]
+    fn grant_global_and_scoped_roles() {
+        let mut acl = setup();
+        assert!(acl.acl_has_role(Role::Owner, accounts(0), None));
+        acl.acl_grant_role(Role::Admin, accounts(1), None);
+        set_caller(accounts(1), ONE_NEAR);
+        acl.acl_grant_role(Role::Minter, accounts(2), None);
+        set_caller(community(), ONE_NEAR);
+        acl.acl_grant_role(Role::CommunityAdmin, accounts(3), Some(community()));
+        set_caller(accounts(3), ONE_NEAR);
+        acl.acl_grant_role(Role::Pauser, accounts(4), Some(community()));
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"role_granted","data":[{"role":"pauser","account_id":"eugene","sender_id":"danny","contract_id":"community"}]"#)));
+
+        assert!(acl.acl_has_role(Role::Minter, accounts(2), Some(community())));
+        assert!(acl.acl_has_role(Role::Pauser, accounts(4), Some(community())));
+        assert!(!acl.acl_has_role(Role::Pauser, accounts(4), None));
+        assert!(acl.acl_has_role(Role::Minter, community(), Some(community())));
+        assert_eq!(acl.acl_role_members(Role::Pauser, Some(community()), None, None), vec![accounts(4)]);
+    }
+
+    #[test]
+    fn revoke_and_renounce() {
+        let mut acl = setup();
+        acl.acl_grant_role(Role::Minter, accounts(1), Some(community()));
+        acl.acl_grant_role(Role::Pauser, accounts(2), None);
+        set_caller(accounts(0), 1);
+        acl.acl_revoke_role(Role::Minter, accounts(1), Some(community()));
+        assert!(!acl.acl_has_role(Role::Minter, accounts(1), Some(community())));
+        set_caller(accounts(2), 1);
+        acl.acl_renounce_role(Role::Pauser, None);
+        assert!(get_logs().last().unwrap().contains(r#""event":"role_revoked","data":[{"role":"pauser","account_id":"charlie","sender_id":"charlie"}]"#));
+        assert!(acl.acl_role_members(Role::Pauser, None, None, None).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of at least 1 yoctoNEAR")]
+    fn grant_without_deposit() {
+        let mut acl = setup();
+        acl.acl_grant_role(Role::Minter, accounts(1), None);
+        set_caller(accounts(0), 0);
+        acl.acl_grant_role(Role::Minter, accounts(1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The caller cannot manage this role")]
+    fn community_admin_cannot_grant_globally() {
+        let mut acl = setup();
+        set_caller(community(), ONE_NEAR);
+        acl.acl_grant_role(Role::CommunityAdmin, accounts(3), Some(community()));
+        set_caller(accounts(3), ONE_NEAR);
+        acl.acl_grant_role(Role::Minter, accounts(4), None);
+    }
+
+    #[test]
+    fn timelocked_community_grant() {
+        let mut acl = setup();
+        acl.timelocked = true;
+        set_caller(community(), ONE_NEAR);
+        acl.acl_grant_role(Role::Minter, accounts(1), Some(community()));
+        assert!(acl.acl_has_role(Role::Minter, accounts(1), Some(community())));
+    }
+
+    #[test]
+    #[should_panic(expected = "The change has to go through the admin timelock")]
+    fn timelocked_owner_grant() {
+        let mut acl = setup();
+        acl.timelocked = true;
+        acl.acl_grant_role(Role::Minter, accounts(1), Some(community()));
+    }
+
+    #[test]
+    #[should_panic(expected = "The last owner cannot be removed")]
+    fn renounce_last_owner() {
+        let mut acl = setup();
+        acl.acl_grant_role(Role::Owner, accounts(1), None);
+        set_caller(accounts(0), 1);
+        acl.acl_revoke_role(Role::Owner, accounts(1), None);
+        acl.acl_renounce_role(Role::Owner, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The change has to go through the admin timelock")]
+    fn timelocked_admin_renounce() {
+        let mut acl = setup();
+        acl.acl_grant_role(Role::Admin, accounts(1), None);
+        acl.timelocked = true;
+        set_caller(accounts(1), 1);
+        acl.acl_renounce_role(Role::Admin, None);
+    }
+
+    #[test]
+    fn timelocked_minter_renounce() {
+        let mut acl = setup();
+        acl.acl_grant_role(Role::Minter, accounts(1), None);
+        acl.timelocked = true;
+        set_caller(accounts(1), 1);
+        acl.acl_renounce_role(Role::Minter, None);
+        assert!(!acl.acl_has_role(Role::Minter, accounts(1), None));
+    }
+
+    #[test]
+    #[should_panic(expected = "The owner and admin roles can only be held globally")]
+    fn scoped_admin() {
+        let mut acl = setup();
+        acl.acl_grant_role(Role::Admin, accounts(1), Some(community()));
+    }
+}
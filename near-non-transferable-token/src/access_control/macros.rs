@@ -0,0 +1,97 @@
+/// The methods to grant, revoke, renounce and look up the roles.
+/// Takes name of the Contract struct and the inner field for the roles.
+#[macro_export]
+macro_rules! impl_access_control {
+    ($contract: ident, $acl: ident) => {
+
+        #[near_bindgen]
+        impl AccessControlCore for $contract {
+
+            #[payable]
+            fn acl_grant_role(&mut self, role: Role, account_id: AccountId, contract_id: Option<AccountId>) {
+                self.$acl.acl_grant_role(role, account_id, contract_id)
+            }
+
+            #[payable]
+            fn acl_revoke_role(&mut self, role: Role, account_id: AccountId, contract_id: Option<AccountId>) {
+                self.$acl.acl_revoke_role(role, account_id, contract_id)
+            }
+
+            #[payable]
+            fn acl_renounce_role(&mut self, role: Role, contract_id: Option<AccountId>) {
+                self.$acl.acl_renounce_role(role, contract_id)
+            }
+
+            fn acl_has_role(&self, role: Role, account_id: AccountId, contract_id: Option<AccountId>) -> bool {
+                self.$acl.acl_has_role(role, account_id, contract_id)
+            }
+
+            fn acl_role_members(
+                &self,
+                role: Role,
+                contract_id: Option<AccountId>,
+                from_index: Option<U128>,
+                limit: Option<u64>,
+            ) -> Vec<AccountId> {
+                self.$acl.acl_role_members(role, contract_id, from_index, limit)
+            }
+        }
+    };
+}
+
+/// Panics unless the predecessor holds the role, globally or in the given community.
+/// E.g. `require_role!(self.acl, Role::Minter, &contract_id)` at the top of a mint method.
+#[macro_export]
+macro_rules! require_role {
+    ($acl: expr, $role: expr) => {
+        $acl.assert_role($role, &near_sdk::env::predecessor_account_id(), None)
+    };
+    ($acl: expr, $role: expr, $contract_id: expr) => {
+        $acl.assert_role($role, &near_sdk::env::predecessor_account_id(), Some($contract_id))
+    };
+}
+
+/// The queue of admin actions, approved by the owners and admins of the roles and executed on
+/// the roles and the token after a delay.
+/// Takes name of the Contract struct and the inner fields for the queue, the roles and the token.
+#[macro_export]
+macro_rules! impl_admin_timelock {
+    ($contract: ident, $timelock: ident, $acl: ident, $token: ident) => {
+
+        #[near_bindgen]
+        impl AdminTimelockCore for $contract {
+
+            #[payable]
+            fn admin_propose(&mut self, action: AdminAction) -> U64 {
+                self.$timelock.propose(&self.$acl, action)
+            }
+
+            #[payable]
+            fn admin_approve(&mut self, id: U64) {
+                self.$timelock.approve(&self.$acl, id)
+            }
+
+            #[payable]
+            fn admin_cancel(&mut self, id: U64) {
+                self.$timelock.cancel(&self.$acl, id)
+            }
+
+            #[payable]
+            fn admin_execute(&mut self, id: U64) {
+                self.$timelock.execute(&mut self.$acl, &mut self.$token, id)
+            }
+
+            fn admin_proposal(&self, id: U64) -> Option<AdminProposal> {
+                self.$timelock.proposal(id)
+            }
+
+            fn admin_proposals(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<AdminProposal> {
+                self.$timelock.proposals(from_index, limit)
+            }
+
+            fn admin_timelock_config(&self) -> TimelockConfig {
+                self.$timelock.config()
+            }
+        }
+    };
+}
@@ -0,0 +1,8 @@
+pub mod core;
+pub mod core_impl;
+pub mod macros;
+pub mod timelock;
+
+pub use core::Role;
+pub use core_impl::AccessControl;
+pub use timelock::AdminTimelock;
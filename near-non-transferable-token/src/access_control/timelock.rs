@@ -0,0 +1,479 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedMap;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId, Balance, IntoStorageKey, Promise};
+
+use crate::access_control::core::Role;
+use crate::access_control::core_impl::AccessControl;
+use crate::fungible_token::core_impl::FungibleToken;
+use crate::fungible_token::events::{AdminActionApproved, AdminActionCancelled, AdminActionExecuted, AdminActionProposed, AdminCancelApproved};
+use crate::fungible_token::pause::Pause;
+use crate::fungible_token::storage_impl::settle_storage_deposit;
+
+/// The changes that go through the proposal queue. Decay policies are not part of this library,
+/// so they have no action; a contract that adds one guards it with its own roles.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum AdminAction {
+    GrantRole { role: Role, account_id: AccountId, contract_id: Option<AccountId> },
+    RevokeRole { role: Role, account_id: AccountId, contract_id: Option<AccountId> },
+    SetPause { pause: Pause },
+    SetCommunityPause { contract_id: AccountId, pause: Pause },
+    SetAccountFreeze { account_id: AccountId, pause: Pause },
+    /// Changes the approvals and the delay of the queue itself.
+    UpdateTimelock { threshold: u32, min_delay: U64 },
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AdminProposal {
+    pub id: U64,
+    pub action: AdminAction,
+    pub proposer_id: AccountId,
+    pub approvals: Vec<AccountId>,
+    pub proposed_at: U64,
+    /// When the approvals reached the threshold. The action can be executed `min_delay` later.
+    pub approved_at: Option<U64>,
+    /// The approvers that voted to cancel the action.
+    pub cancellations: Vec<AccountId>,
+    /// What the proposer paid for the storage of the action, refunded to it when the action is removed.
+    pub storage_deposit: U128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TimelockConfig {
+    pub threshold: u32,
+    pub min_delay: U64,
+}
+
+/// A queue of admin actions. An action is executed once `threshold` of the owners and admins
+/// approved it and `min_delay` passed since, so that the members can see it coming. The
+/// `owner` and `admin` roles of [`AccessControl`] are the approvers, and an approval counts only
+/// while its account still holds one of them.
+#[ext_contract(ext_admin_timelock)]
+pub trait AdminTimelockCore {
+    /// Queues `action`, approved by the caller, and returns its id. Only an owner or an admin
+    /// can call it, with a deposit to cover the added storage.
+    fn admin_propose(&mut self, action: AdminAction) -> U64;
+
+    /// Only an owner or an admin can call it, with 1 yoctoNEAR attached.
+    fn admin_approve(&mut self, id: U64);
+
+    /// Drops a queued action if the caller proposed it, or else votes to drop it, which happens
+    /// once `threshold` of the owners and admins voted. Only an owner or an admin can call it,
+    /// with 1 yoctoNEAR attached.
+    fn admin_cancel(&mut self, id: U64);
+
+    /// Applies an approved action after the delay, or resets its approval if it lost approvers
+    /// since. Anyone can call it, with 1 yoctoNEAR attached.
+    fn admin_execute(&mut self, id: U64);
+
+    fn admin_proposal(&self, id: U64) -> Option<AdminProposal>;
+
+    /// The queued actions, oldest first.
+    fn admin_proposals(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<AdminProposal>;
+
+    fn admin_timelock_config(&self) -> TimelockConfig;
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AdminTimelock {
+    /// Id -> queued action.
+    pub proposals: UnorderedMap<u64, AdminProposal>,
+
+    /// The id of the next action.
+    pub next_id: u64,
+
+    /// The approvals an action needs.
+    pub threshold: u32,
+
+    /// Nanoseconds between the approval of an action and its execution.
+    pub min_delay: u64,
+}
+
+impl AdminTimelock {
+    /// Creates the queue and sets `acl` as timelocked, so that its global roles and the pauses
+    /// only change through the queue.
+    pub fn new<S>(prefix: S, acl: &mut AccessControl, threshold: u32, min_delay: u64) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        assert_valid_threshold(acl, threshold);
+        acl.timelocked = true;
+        Self { proposals: UnorderedMap::new(prefix), next_id: 0, threshold, min_delay }
+    }
+
+    fn assert_approver(&self, acl: &AccessControl, account_id: &AccountId) {
+        require!(is_approver(acl, account_id), "Only an owner or an admin can do this");
+    }
+
+    fn internal_unwrap_proposal(&self, id: u64) -> AdminProposal {
+        let proposal = self.proposals.get(&id);
+        require!(proposal.is_some(), "The action is not queued");
+        proposal.unwrap()
+    }
+
+    /// Drops the approvals of the accounts that are no longer approvers, then starts the delay of
+    /// `proposal` if it has enough approvals, or stops it if it lost some.
+    fn internal_check_approvals(&self, acl: &AccessControl, proposal: &mut AdminProposal) {
+        proposal.approvals.retain(|account_id| is_approver(acl, account_id));
+        if proposal.approvals.len() < self.threshold as usize {
+            proposal.approved_at = None;
+        } else if proposal.approved_at.is_none() {
+            proposal.approved_at = Some(env::block_timestamp().into());
+        }
+    }
+
+    /// Removes the action `id` and refunds the proposer what it paid for its storage. The storage
+    /// added by the approvals was paid by the contract.
+    fn internal_remove_proposal(&mut self, id: u64) -> AdminProposal {
+        let initial_storage_usage = env::storage_usage();
+        let proposal = self.internal_unwrap_proposal(id);
+        self.proposals.remove(&id);
+        let freed = Balance::from(initial_storage_usage.saturating_sub(env::storage_usage())) * env::storage_byte_cost();
+        let refund = freed.min(proposal.storage_deposit.0);
+        if refund > 0 {
+            Promise::new(proposal.proposer_id.clone()).transfer(refund);
+        }
+        proposal
+    }
+
+    pub fn propose(&mut self, acl: &AccessControl, action: AdminAction) -> U64 {
+        let initial_storage_usage = env::storage_usage();
+        let proposer_id = env::predecessor_account_id();
+        self.assert_approver(acl, &proposer_id);
+        match &action {
+            AdminAction::GrantRole { role, contract_id, .. } => {
+                require!(contract_id.is_none() || role.is_scoped(), "The owner and admin roles can only be held globally");
+            }
+            AdminAction::RevokeRole { role, account_id, contract_id } => {
+                require!(contract_id.is_none() || role.is_scoped(), "The owner and admin roles can only be held globally");
+                assert_valid_revoke(acl, self.threshold, *role, account_id);
+            }
+            AdminAction::UpdateTimelock { threshold, .. } => assert_valid_threshold(acl, *threshold),
+            _ => {}
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut proposal = AdminProposal {
+            id: id.into(),
+            action,
+            proposer_id: proposer_id.clone(),
+            approvals: vec![proposer_id.clone()],
+            proposed_at: env::block_timestamp().into(),
+            approved_at: None,
+            cancellations: vec![],
+            storage_deposit: U128(0),
+        };
+        self.internal_check_approvals(acl, &mut proposal);
+        self.proposals.insert(&id, &proposal);
+        // The deposit has a fixed size, so writing it again does not change the storage it paid for.
+        proposal.storage_deposit = (Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost()).into();
+        self.proposals.insert(&id, &proposal);
+        AdminActionProposed { id: &proposal.id, proposer_id: &proposer_id, action: &proposal.action }.emit();
+        settle_storage_deposit(initial_storage_usage);
+        proposal.id
+    }
+
+    pub fn approve(&mut self, acl: &AccessControl, id: U64) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.assert_approver(acl, &sender_id);
+        let mut proposal = self.internal_unwrap_proposal(id.0);
+        require!(!proposal.approvals.contains(&sender_id), "The action is already approved by the caller");
+        proposal.approvals.push(sender_id.clone());
+        self.internal_check_approvals(acl, &mut proposal);
+        self.proposals.insert(&id.0, &proposal);
+        AdminActionApproved { id: &id, sender_id: &sender_id }.emit();
+    }
+
+    pub fn cancel(&mut self, acl: &AccessControl, id: U64) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.assert_approver(acl, &sender_id);
+        let mut proposal = self.internal_unwrap_proposal(id.0);
+        if proposal.proposer_id != sender_id {
+            require!(!proposal.cancellations.contains(&sender_id), "The cancel is already approved by the caller");
+            proposal.cancellations.push(sender_id.clone());
+            AdminCancelApproved { id: &id, sender_id: &sender_id }.emit();
+            let cancellations = proposal.cancellations.iter().filter(|account_id| is_approver(acl, account_id)).count();
+            if cancellations < self.threshold as usize {
+                self.proposals.insert(&id.0, &proposal);
+                return;
+            }
+        }
+        self.internal_remove_proposal(id.0);
+        AdminActionCancelled { id: &id, sender_id: &sender_id }.emit();
+    }
+
+    /// Applies the action `id` to `acl`, `token` or the queue itself. The changes are logged
+    /// with the contract account as their sender. If approvers lost their role since the action
+    /// was approved and it falls below the threshold, its approval is reset instead, and the
+    /// delay starts again once it has enough approvals.
+    pub fn execute(&mut self, acl: &mut AccessControl, token: &mut FungibleToken, id: U64) {
+        assert_one_yocto();
+        let mut proposal = self.internal_unwrap_proposal(id.0);
+        require!(proposal.approved_at.is_some(), "The action is not approved");
+        self.internal_check_approvals(acl, &mut proposal);
+        let approved_at = match proposal.approved_at {
+            Some(approved_at) => approved_at.0,
+            None => {
+                self.proposals.insert(&id.0, &proposal);
+                return;
+            }
+        };
+        require!(env::block_timestamp() >= approved_at.saturating_add(self.min_delay), "The action is still delayed");
+        if let AdminAction::RevokeRole { role, account_id, .. } = &proposal.action {
+            assert_valid_revoke(acl, self.threshold, *role, account_id);
+        }
+        self.internal_remove_proposal(id.0);
+
+        let sender_id = env::current_account_id();
+        match proposal.action {
+            AdminAction::GrantRole { role, account_id, contract_id } => {
+                acl.internal_grant_role(role, &account_id, contract_id.as_ref(), &sender_id);
+            }
+            AdminAction::RevokeRole { role, account_id, contract_id } => {
+                acl.internal_revoke_role(role, &account_id, contract_id.as_ref(), &sender_id);
+            }
+            AdminAction::SetPause { pause } => token.internal_set_pause(&sender_id, pause),
+            AdminAction::SetCommunityPause { contract_id, pause } => {
+                token.internal_set_community_pause(&sender_id, &contract_id, pause)
+            }
+            AdminAction::SetAccountFreeze { account_id, pause } => {
+                token.internal_set_account_freeze(&sender_id, &account_id, pause)
+            }
+            AdminAction::UpdateTimelock { threshold, min_delay } => {
+                assert_valid_threshold(acl, threshold);
+                self.threshold = threshold;
+                self.min_delay = min_delay.0;
+            }
+        }
+        AdminActionExecuted { id: &id, sender_id: &env::predecessor_account_id() }.emit();
+    }
+
+    pub fn proposal(&self, id: U64) -> Option<AdminProposal> {
+        self.proposals.get(&id.0)
+    }
+
+    pub fn proposals(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<AdminProposal> {
+        self.proposals
+            .values()
+            .skip(from_index.map_or(0, |index| index.0 as usize))
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect()
+    }
+
+    pub fn config(&self) -> TimelockConfig {
+        TimelockConfig { threshold: self.threshold, min_delay: self.min_delay.into() }
+    }
+}
+
+fn is_approver(acl: &AccessControl, account_id: &AccountId) -> bool {
+    acl.has_role(Role::Owner, account_id, None) || acl.has_role(Role::Admin, account_id, None)
+}
+
+/// The owners and admins, each once.
+fn approvers(acl: &AccessControl) -> Vec<AccountId> {
+    let mut approvers: Vec<AccountId> = vec![];
+    for role in [Role::Owner, Role::Admin] {
+        for account_id in acl.members.get(&(role, None)).iter().flat_map(|members| members.iter()) {
+            if !approvers.contains(&account_id) {
+                approvers.push(account_id);
+            }
+        }
+    }
+    approvers
+}
+
+/// Panics unless `threshold` is between 1 and the number of owners and admins.
+fn assert_valid_threshold(acl: &AccessControl, threshold: u32) {
+    require!(threshold > 0, "The threshold should be a positive number");
+    require!(threshold as usize <= approvers(acl).len(), "The threshold should not exceed the number of owners and admins");
+}
+
+/// Panics if revoking `role` from `account_id` would leave fewer owners and admins than
+/// `threshold`, so that the queue could no longer approve anything.
+fn assert_valid_revoke(acl: &AccessControl, threshold: u32, role: Role, account_id: &AccountId) {
+    let other_role = match role {
+        Role::Owner => Role::Admin,
+        Role::Admin => Role::Owner,
+        _ => return
+    };
+    let mut remaining = approvers(acl).len();
+    if acl.has_role(role, account_id, None) && !acl.has_role(other_role, account_id, None) {
+        remaining -= 1;
+    }
+    require!(threshold as usize <= remaining, "The revoke would leave fewer owners and admins than the threshold");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{community, set_caller_at as set_caller};
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs};
+    use near_sdk::ONE_NEAR;
+
+    const DELAY: u64 = 1_000;
+
+    fn setup() -> (AdminTimelock, AccessControl, FungibleToken) {
+        set_caller(accounts(0), ONE_NEAR, 0);
+        let mut acl = AccessControl::new(b"r".to_vec(), &accounts(0));
+        acl.internal_grant_role(Role::Admin, &accounts(1), None, &accounts(0));
+        acl.internal_grant_role(Role::Admin, &accounts(2), None, &accounts(0));
+        (AdminTimelock::new(b"q".to_vec(), &mut acl, 2, DELAY), acl, FungibleToken::new(b"t".to_vec()))
+    }
+
+    #[test]
+    fn approve_and_execute_after_delay() {
+        let (mut timelock, mut acl, mut token) = setup();
+        let action = AdminAction::GrantRole { role: Role::Minter, account_id: accounts(3), contract_id: Some(community()) };
+        let id = timelock.propose(&acl, action);
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"admin_action_proposed","data":[{"id":"0","proposer_id":"alice","action":{"grant_role":{"role":"minter","account_id":"danny","contract_id":"community"}}}]"#)));
+        assert_eq!(timelock.proposal(id).unwrap().approved_at, None);
+
+        set_caller(accounts(1), 1, 10);
+        timelock.approve(&acl, id);
+        assert_eq!(timelock.proposals(None, None)[0].approved_at, Some(U64(10)));
+
+        set_caller(accounts(4), 1, 10 + DELAY);
+        timelock.execute(&mut acl, &mut token, id);
+        assert!(acl.has_role(Role::Minter, &accounts(3), Some(&community())));
+        assert!(timelock.proposal(id).is_none());
+    }
+
+    #[test]
+    fn update_pause_and_timelock() {
+        let (mut timelock, mut acl, mut token) = setup();
+        let pause = Pause { mint: true, ..Default::default() };
+        let pause_id = timelock.propose(&acl, AdminAction::SetPause { pause });
+        let update_id = timelock.propose(&acl, AdminAction::UpdateTimelock { threshold: 3, min_delay: U64(0) });
+        set_caller(accounts(2), 1, 0);
+        timelock.approve(&acl, pause_id);
+        timelock.approve(&acl, update_id);
+        set_caller(accounts(2), 1, DELAY);
+        timelock.execute(&mut acl, &mut token, pause_id);
+        timelock.execute(&mut acl, &mut token, update_id);
+        assert_eq!(token.pause_status(None, None), pause);
+        assert_eq!(timelock.config(), TimelockConfig { threshold: 3, min_delay: U64(0) });
+    }
+
+    #[test]
+    fn execute_with_revoked_approver_resets_the_approval() {
+        let (mut timelock, mut acl, mut token) = setup();
+        let pause = Pause { mint: true, ..Default::default() };
+        let id = timelock.propose(&acl, AdminAction::SetPause { pause });
+        set_caller(accounts(1), 1, 0);
+        timelock.approve(&acl, id);
+        acl.internal_revoke_role(Role::Admin, &accounts(1), None, &accounts(0));
+        set_caller(accounts(2), 1, DELAY);
+        timelock.execute(&mut acl, &mut token, id);
+        let proposal = timelock.proposal(id).unwrap();
+        assert_eq!(proposal.approvals, vec![accounts(0)]);
+        assert_eq!(proposal.approved_at, None);
+        assert_eq!(token.pause_status(None, None), Pause::default());
+
+        timelock.approve(&acl, id);
+        assert_eq!(timelock.proposal(id).unwrap().approved_at, Some(U64(DELAY)));
+        set_caller(accounts(2), 1, 2 * DELAY);
+        timelock.execute(&mut acl, &mut token, id);
+        assert_eq!(token.pause_status(None, None), pause);
+    }
+
+    #[test]
+    #[should_panic(expected = "The action is not approved")]
+    fn execute_unapproved() {
+        let (mut timelock, mut acl, mut token) = setup();
+        let id = timelock.propose(&acl, AdminAction::SetPause { pause: Pause::default() });
+        set_caller(accounts(1), 1, DELAY);
+        timelock.execute(&mut acl, &mut token, id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The revoke would leave fewer owners and admins than the threshold")]
+    fn revoke_below_threshold() {
+        let (mut timelock, mut acl, _) = setup();
+        acl.internal_revoke_role(Role::Admin, &accounts(2), None, &accounts(0));
+        timelock.propose(&acl, AdminAction::RevokeRole { role: Role::Admin, account_id: accounts(1), contract_id: None });
+    }
+
+    #[test]
+    #[should_panic(expected = "The revoke would leave fewer owners and admins than the threshold")]
+    fn execute_revoke_below_threshold() {
+        let (mut timelock, mut acl, mut token) = setup();
+        acl.internal_grant_role(Role::Admin, &accounts(3), None, &accounts(0));
+        timelock.threshold = 3;
+        let id = timelock.propose(&acl, AdminAction::RevokeRole { role: Role::Admin, account_id: accounts(2), contract_id: None });
+        set_caller(accounts(1), 1, 0);
+        timelock.approve(&acl, id);
+        set_caller(accounts(2), 1, 0);
+        timelock.approve(&acl, id);
+        acl.internal_revoke_role(Role::Admin, &accounts(3), None, &accounts(0));
+        set_caller(accounts(2), 1, DELAY);
+        timelock.execute(&mut acl, &mut token, id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The threshold should not exceed the number of owners and admins")]
+    fn update_threshold_above_approvers() {
+        let (mut timelock, acl, _) = setup();
+        timelock.propose(&acl, AdminAction::UpdateTimelock { threshold: 4, min_delay: U64(0) });
+    }
+
+    #[test]
+    #[should_panic(expected = "The action is still delayed")]
+    fn execute_before_delay() {
+        let (mut timelock, mut acl, mut token) = setup();
+        let id = timelock.propose(&acl, AdminAction::SetPause { pause: Pause::default() });
+        set_caller(accounts(1), 1, 10);
+        timelock.approve(&acl, id);
+        timelock.execute(&mut acl, &mut token, id);
+    }
+
+    #[test]
+    #[should_panic(expected = "The action is not queued")]
+    fn execute_cancelled() {
+        let (mut timelock, mut acl, mut token) = setup();
+        let id = timelock.propose(&acl, AdminAction::SetPause { pause: Pause::default() });
+        set_caller(accounts(1), 1, 0);
+        timelock.approve(&acl, id);
+        set_caller(accounts(0), 1, 0);
+        timelock.cancel(&acl, id);
+        set_caller(accounts(1), 1, DELAY);
+        timelock.execute(&mut acl, &mut token, id);
+    }
+
+    #[test]
+    fn cancel_refunds_the_proposer_deposit() {
+        let (mut timelock, acl, _) = setup();
+        let id = timelock.propose(&acl, AdminAction::SetPause { pause: Pause::default() });
+        let storage_deposit = timelock.proposal(id).unwrap().storage_deposit.0;
+        assert!(storage_deposit > 0);
+        set_caller(accounts(1), 1, 0);
+        timelock.approve(&acl, id);
+        set_caller(accounts(0), 1, 0);
+        timelock.cancel(&acl, id);
+        assert!(timelock.proposal(id).is_none());
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.last().unwrap().receiver_id, accounts(0));
+        assert_eq!(receipts.last().unwrap().actions, vec![VmAction::Transfer { deposit: storage_deposit }]);
+    }
+
+    #[test]
+    fn cancel_by_threshold_of_approvers() {
+        let (mut timelock, acl, _) = setup();
+        let id = timelock.propose(&acl, AdminAction::SetPause { pause: Pause::default() });
+        set_caller(accounts(1), 1, 0);
+        timelock.cancel(&acl, id);
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"admin_cancel_approved","data":[{"id":"0","sender_id":"bob"}]"#)));
+        assert_eq!(timelock.proposal(id).unwrap().cancellations, vec![accounts(1)]);
+        set_caller(accounts(2), 1, 0);
+        timelock.cancel(&acl, id);
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"admin_action_cancelled","data":[{"id":"0","sender_id":"charlie"}]"#)));
+        assert!(timelock.proposal(id).is_none());
+    }
+}
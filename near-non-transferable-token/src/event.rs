@@ -6,8 +6,11 @@ use serde::Serialize;
 #[must_use = "don't forget to `.emit()` this event"]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum NearEvent<'a> {
-    // Nep171(crate::non_fungible_token::events::Nep171Event<'a>),
-    Nep141(crate::fungible_token::events::Nep141Event<'a>),
+    Nep171(crate::non_fungible_token::events::Nep171Event<'a>),
+    Nep393(crate::non_fungible_token::events::Nep393Event<'a>),
+    Ntt(crate::fungible_token::events::NttEvent<'a>),
+    #[cfg(feature = "nep141-events")]
+    Nep141(crate::fungible_token::events::nep141::Nep141Event<'a>),
 }
 
 impl<'a> NearEvent<'a> {
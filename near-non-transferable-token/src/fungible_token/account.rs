@@ -6,6 +6,10 @@ pub trait FungibleTokenAccount {
 
     fn withdraw(&mut self, contract_id: &AccountId, amount: Balance) -> u128;
 
+    /// Returns a previously withdrawn `amount` to the available balance without counting it
+    /// towards the total balance again.
+    fn refund(&mut self, contract_id: &AccountId, amount: Balance);
+
     fn contract_deposit(&mut self, contract_id: &AccountId, deposit_contract_id: &AccountId, amount: Balance);
 
     fn contract_withdraw(&mut self, contract_id: &AccountId, deposit_contract_id: &AccountId, amount: Balance);
@@ -14,6 +18,10 @@ pub trait FungibleTokenAccount {
 
     fn get_deposit_balance(&self, contract_id: &Option<AccountId>, deposit_contract_id: &Option<AccountId>) -> u128;
 
+    fn get_deposit_expires_at(&self, contract_id: &AccountId, deposit_contract_id: &AccountId) -> Option<u64>;
+
+    fn set_deposit_expires_at(&mut self, contract_id: &AccountId, deposit_contract_id: &AccountId, expires_at: Option<u64>);
+
     fn get_total_balance(&self, contract_id: &Option<AccountId>) -> u128;
 
     fn is_registered(&self, contract_id: &AccountId) -> bool;
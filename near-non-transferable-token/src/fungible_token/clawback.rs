@@ -0,0 +1,205 @@
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId, Balance, Gas};
+
+use crate::access_control::{AccessControl, Role};
+use crate::fungible_token::account::FungibleTokenAccount;
+use crate::fungible_token::core_impl::FungibleToken;
+use crate::fungible_token::events::{FtClawback, FtSlash};
+use crate::fungible_token::pause::PausableOperation;
+use crate::fungible_token::receiver::ext_ft_slash_receiver;
+
+/// `ft_on_slash` is a notification: its result is not awaited and nothing is undone if it fails.
+const GAS_FOR_FT_ON_SLASH: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_FT_SLASH: Gas = Gas(10_000_000_000_000 + GAS_FOR_FT_ON_SLASH.0);
+
+/// Burns that a community runs against the holders of its tokens, to take back tokens awarded by
+/// mistake or earned by abuse. The tokens of community `contract_id`, the caller's own if unset,
+/// are burned by the community itself or one of its community admins. Like `ft_burn`, these lower
+/// the available balance and the available supply, while the total balance keeps what the holder
+/// received. Both stop while burning is paused for the holder or the community.
+#[ext_contract(ext_ft_clawback)]
+pub trait FungibleTokenClawback {
+    /// Burns `amount` of the community's tokens from the available balance of `owner_id`.
+    /// Requires 1 yoctoNEAR attached.
+    fn ft_clawback(&mut self, owner_id: AccountId, amount: U128, reason: Option<String>, contract_id: Option<AccountId>);
+
+    /// Burns `amount` of the community's tokens from `owner_id`, taking the available balance
+    /// first and the rest from the deposit held with `deposit_contract_id`, which is then notified
+    /// through `ft_on_slash`. The notification gets a fixed amount of gas and has no callback, so
+    /// the slash stands even if the receiver fails. Requires 1 yoctoNEAR attached.
+    fn ft_slash(&mut self, owner_id: AccountId, deposit_contract_id: AccountId, amount: U128, reason: Option<String>, contract_id: Option<AccountId>);
+}
+
+impl FungibleToken {
+    /// Burns `amount` of the deposit `owner_id` holds with `deposit_contract_id`.
+    pub fn internal_slash_deposit(&mut self, owner_id: &AccountId, amount: Balance, contract_id: &AccountId, deposit_contract_id: &AccountId) {
+        let mut account = self.internal_unwrap_migrated_account(owner_id);
+        let deposit_balance = account.get_deposit_balance(&Some(contract_id.clone()), &Some(deposit_contract_id.clone()));
+        require!(deposit_balance >= amount, "Not enough balance to slash");
+        account.contract_withdraw(contract_id, deposit_contract_id, amount);
+        self.accounts.insert(owner_id, &account);
+        self.deposit_registry.withdraw(deposit_contract_id, contract_id, owner_id, amount);
+        self.total_supply.withdraw(contract_id, amount);
+    }
+}
+
+impl FungibleToken {
+    /// The community whose tokens the caller burns: `contract_id` if set, where `acts_for` tells
+    /// whether the caller can act for it, or else the caller itself.
+    fn internal_clawback_community(&self, contract_id: Option<AccountId>, acts_for: impl Fn(&AccountId) -> bool) -> AccountId {
+        let sender_id = env::predecessor_account_id();
+        match contract_id {
+            Some(contract_id) => {
+                require!(contract_id == sender_id || acts_for(&contract_id), "Only the community or its community admins can burn its tokens");
+                contract_id
+            }
+            None => sender_id
+        }
+    }
+
+    fn internal_clawback(&mut self, contract_id: AccountId, owner_id: AccountId, amount: U128, reason: Option<String>) {
+        self.internal_assert_not_paused(PausableOperation::Burn, &owner_id, &contract_id);
+        self.internal_withdraw(&owner_id, amount.0, &contract_id);
+        FtClawback { owner_id: &owner_id, contract_id: &contract_id, amount: &amount, reason: reason.as_deref() }.emit();
+    }
+
+    fn internal_slash(&mut self, contract_id: AccountId, owner_id: AccountId, deposit_contract_id: AccountId, amount: U128, reason: Option<String>) {
+        self.internal_assert_not_paused(PausableOperation::Burn, &owner_id, &contract_id);
+        let available = self.internal_unwrap_migrated_account(&owner_id).get_available_balance(&Some(contract_id.clone()));
+        let available_amount = available.min(amount.0);
+        let deposit_amount = amount.0 - available_amount;
+        if available_amount > 0 {
+            self.internal_withdraw(&owner_id, available_amount, &contract_id);
+            FtClawback { owner_id: &owner_id, contract_id: &contract_id, amount: &available_amount.into(), reason: reason.as_deref() }.emit();
+        }
+        if deposit_amount > 0 {
+            self.internal_slash_deposit(&owner_id, deposit_amount, &contract_id, &deposit_contract_id);
+            FtSlash {
+                owner_id: &owner_id,
+                contract_id: &contract_id,
+                deposit_contract_id: &deposit_contract_id,
+                amount: &deposit_amount.into(),
+                reason: reason.as_deref(),
+            }
+            .emit();
+            ext_ft_slash_receiver::ext(deposit_contract_id)
+                .with_static_gas(GAS_FOR_FT_ON_SLASH)
+                .ft_on_slash(owner_id, contract_id, deposit_amount.into(), reason);
+        }
+    }
+
+    /// Claws back like `ft_clawback`, where a community admin of `contract_id` in `acl` acts for it.
+    pub fn clawback(&mut self, acl: &AccessControl, owner_id: AccountId, amount: U128, reason: Option<String>, contract_id: Option<AccountId>) {
+        assert_one_yocto();
+        require!(amount.0 > 0, "The amount should be a positive number");
+        let sender_id = env::predecessor_account_id();
+        let contract_id = self.internal_clawback_community(contract_id, |contract_id| acl.has_community_role(Role::CommunityAdmin, &sender_id, contract_id));
+        self.internal_clawback(contract_id, owner_id, amount, reason);
+    }
+
+    /// Slashes like `ft_slash`, where a community admin of `contract_id` in `acl` acts for it.
+    pub fn slash(&mut self, acl: &AccessControl, owner_id: AccountId, deposit_contract_id: AccountId, amount: U128, reason: Option<String>, contract_id: Option<AccountId>) {
+        assert_one_yocto();
+        require!(amount.0 > 0, "The amount should be a positive number");
+        require!(env::prepaid_gas() > GAS_FOR_FT_SLASH, "More gas is required");
+        let sender_id = env::predecessor_account_id();
+        let contract_id = self.internal_clawback_community(contract_id, |contract_id| acl.has_community_role(Role::CommunityAdmin, &sender_id, contract_id));
+        self.internal_slash(contract_id, owner_id, deposit_contract_id, amount, reason);
+    }
+}
+
+impl FungibleTokenClawback for FungibleToken {
+    fn ft_clawback(&mut self, owner_id: AccountId, amount: U128, reason: Option<String>, contract_id: Option<AccountId>) {
+        assert_one_yocto();
+        require!(amount.0 > 0, "The amount should be a positive number");
+        let contract_id = self.internal_clawback_community(contract_id, |_| false);
+        self.internal_clawback(contract_id, owner_id, amount, reason);
+    }
+
+    fn ft_slash(&mut self, owner_id: AccountId, deposit_contract_id: AccountId, amount: U128, reason: Option<String>, contract_id: Option<AccountId>) {
+        assert_one_yocto();
+        require!(amount.0 > 0, "The amount should be a positive number");
+        require!(env::prepaid_gas() > GAS_FOR_FT_SLASH, "More gas is required");
+        let contract_id = self.internal_clawback_community(contract_id, |_| false);
+        self.internal_slash(contract_id, owner_id, deposit_contract_id, amount, reason);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::core::FungibleTokenCore;
+    use crate::fungible_token::pause::Pause;
+    use crate::test_utils::{community, registered_token, set_caller};
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    fn setup() -> FungibleToken {
+        let mut token = registered_token(community(), 1);
+        token.internal_deposit(&accounts(1), 1000, &community());
+        token.internal_contract_deposit(&accounts(1), 400, &community(), &accounts(4));
+        token
+    }
+
+    #[test]
+    fn clawback() {
+        let mut token = setup();
+        token.ft_clawback(accounts(1), U128(100), Some("mistake".to_string()), None);
+        assert!(get_logs().last().unwrap().contains(r#""event":"ft_clawback","data":[{"owner_id":"bob","contract_id":"community","amount":"100","reason":"mistake"}]"#));
+        assert_eq!(token.ft_balance_of(accounts(1), Some(community())).0, 500);
+        assert_eq!(token.ft_available_supply(Some(community())).0, 900);
+    }
+
+    #[test]
+    fn clawback_by_community_admin() {
+        let mut token = setup();
+        let mut acl = AccessControl::new(b"r".to_vec(), &accounts(0));
+        acl.internal_grant_role(Role::CommunityAdmin, &accounts(2), Some(&community()), &community());
+        set_caller(accounts(2), 1);
+        token.clawback(&acl, accounts(1), U128(100), None, Some(community()));
+        assert_eq!(token.ft_balance_of(accounts(1), Some(community())).0, 500);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the community or its community admins can burn its tokens")]
+    fn clawback_for_other_community() {
+        let mut token = setup();
+        let acl = AccessControl::new(b"r".to_vec(), &accounts(0));
+        set_caller(accounts(2), 1);
+        token.clawback(&acl, accounts(1), U128(100), None, Some(community()));
+    }
+
+    #[test]
+    fn slash_into_deposit() {
+        let mut token = setup();
+        token.ft_slash(accounts(1), accounts(4), U128(700), None, None);
+        assert_eq!(token.ft_balance_of(accounts(1), Some(community())).0, 0);
+        let account = token.internal_unwrap_account(&accounts(1));
+        assert_eq!(account.get_deposit_balance(&Some(community()), &Some(accounts(4))), 300);
+        assert_eq!(token.deposit_registry.depositors.get(&(accounts(4), community())).unwrap().get(&accounts(1)), Some(300));
+        assert_eq!(token.ft_available_supply(Some(community())).0, 300);
+        assert_eq!(token.ft_total_supply(Some(community())).0, 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Burning is paused for this community")]
+    fn clawback_while_paused() {
+        let mut token = setup();
+        token.community_pauses.insert(&community(), &Pause { burn: true, ..Default::default() });
+        token.ft_clawback(accounts(1), U128(100), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Burning is frozen for this account")]
+    fn slash_frozen_account() {
+        let mut token = setup();
+        token.account_freezes.insert(&accounts(1), &Pause { burn: true, ..Default::default() });
+        token.ft_slash(accounts(1), accounts(4), U128(700), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough balance to slash")]
+    fn slash_more_than_deposit() {
+        let mut token = setup();
+        token.ft_slash(accounts(1), accounts(4), U128(1001), None, None);
+    }
+}
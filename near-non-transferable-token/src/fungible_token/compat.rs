@@ -0,0 +1,84 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, ext_contract, AccountId, PromiseOrValue};
+
+use crate::fungible_token::core_impl::FungibleToken;
+
+/// The transfer methods of NEP-141, for wallets and explorers that expect them.
+/// The token is non-transferable, so both always fail.
+///
+/// The views of NEP-141 need nothing extra: `contract_id` is the optional last argument of
+/// `ft_balance_of` and `ft_total_supply`, so the standard calls get the balance and the supply
+/// across all communities.
+#[ext_contract(ext_ft_transfer_compat)]
+pub trait FungibleTokenTransferCompat {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128>;
+}
+
+impl FungibleTokenTransferCompat for FungibleToken {
+    #[allow(unused_variables)]
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        env::panic_str("The token is non-transferable")
+    }
+
+    #[allow(unused_variables)]
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        env::panic_str("The token is non-transferable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::core::FungibleTokenCore;
+    use crate::fungible_token::resolver::FungibleTokenResolver;
+    use crate::test_utils::registered_token;
+    use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+    use near_sdk::json_types::U64;
+    use near_sdk::near_bindgen;
+    use near_sdk::test_utils::accounts;
+
+    #[near_bindgen]
+    #[derive(BorshDeserialize, BorshSerialize)]
+    struct Contract {
+        token: FungibleToken,
+    }
+
+    crate::impl_fungible_token_core!(Contract, token);
+    crate::impl_fungible_token_nep141_compat!(Contract, token);
+
+    fn setup() -> FungibleToken {
+        let mut token = registered_token(accounts(1), 1);
+        token.internal_deposit(&accounts(1), 100, &accounts(2));
+        token.internal_deposit(&accounts(1), 50, &accounts(3));
+        token
+    }
+
+    #[test]
+    fn aggregate_views() {
+        let token = setup();
+        assert_eq!(token.ft_balance_of(accounts(1), None).0, 150);
+        assert_eq!(token.ft_total_supply(None).0, 150);
+    }
+
+    #[test]
+    fn contract_views() {
+        let contract = Contract { token: setup() };
+        assert_eq!(contract.ft_balance_of(accounts(1), None).0, 150);
+        assert_eq!(contract.ft_balance_of(accounts(1), Some(accounts(2))).0, 100);
+        assert_eq!(contract.ft_total_supply(None).0, 150);
+    }
+}
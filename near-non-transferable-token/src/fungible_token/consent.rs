@@ -0,0 +1,295 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId, Balance};
+
+use crate::fungible_token::core_impl::FungibleToken;
+use crate::fungible_token::events::{ConsentPolicyUpdated, FtMint, FtMintPending, FtMintRejected};
+use crate::fungible_token::pause::PausableOperation;
+
+/// How many mints of a community can wait for an account at once. The contract pays for their
+/// storage, so further mints of the community fail until the account accepts or rejects them.
+pub const MAX_PENDING_MINTS: usize = 32;
+
+/// How many mints can wait for an account at once, across communities. Any account can mint as a
+/// community, so this bounds the pending mints an account reads and writes on each change.
+pub const MAX_TOTAL_PENDING_MINTS: usize = 128;
+
+/// The longest memo, in bytes, a pending mint can keep.
+pub const MAX_PENDING_MINT_MEMO_LEN: usize = 256;
+
+/// Which mints an account accepts.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum ConsentPolicy {
+    /// Every mint is credited right away.
+    AcceptAll,
+    /// Mints of the listed communities are credited right away, and the others fail.
+    AllowList(Vec<AccountId>),
+    /// Every mint waits for the account to accept or reject it.
+    Pending,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingMint {
+    pub contract_id: AccountId,
+    pub amount: U128,
+    pub memo: Option<String>,
+}
+
+/// Lets an account choose which mints it accepts. The mints of a contract have to go through
+/// [`FungibleToken::internal_mint`] for the policy to apply.
+#[ext_contract(ext_ft_consent)]
+pub trait FungibleTokenConsent {
+    /// Sets which mints the caller's account accepts. Mints already pending stay pending.
+    /// Returning to `AcceptAll` refunds the storage it frees. Requires at least 1 yoctoNEAR
+    /// attached, and a deposit to cover the added storage.
+    fn ft_set_consent_policy(&mut self, policy: ConsentPolicy);
+
+    fn ft_consent_policy(&self, account_id: AccountId) -> ConsentPolicy;
+
+    fn ft_pending_mints(&self, account_id: AccountId) -> Vec<PendingMint>;
+
+    /// Credits the caller with the pending mints of `contract_id`, and returns their amount.
+    /// Fails like a mint would if the caller blocked the community since.
+    /// Requires 1 yoctoNEAR attached.
+    fn ft_accept_mints(&mut self, contract_id: AccountId) -> U128;
+
+    /// Drops the pending mints of `contract_id` for the caller, and returns their amount.
+    /// Requires 1 yoctoNEAR attached.
+    fn ft_reject_mints(&mut self, contract_id: AccountId) -> U128;
+}
+
+impl FungibleToken {
+    /// Mints `amount` of community `contract_id` tokens to `owner_id` as its consent policy allows,
+    /// unless it blocked the community, logging `ft_mint`, or `ft_mint_pending` if the mint waits
+    /// for the owner to accept it.
+    /// Returns whether the tokens were credited. The contract pays for the storage of a pending mint,
+    /// so at most [`MAX_PENDING_MINTS`] of a community and [`MAX_TOTAL_PENDING_MINTS`] in all wait
+    /// for an account, with a memo of at most [`MAX_PENDING_MINT_MEMO_LEN`] bytes.
+    pub fn internal_mint(&mut self, owner_id: &AccountId, amount: Balance, contract_id: &AccountId, memo: Option<&str>) -> bool {
+        self.internal_assert_can_receive(owner_id, contract_id);
+        match self.consent_policies.get(owner_id).unwrap_or(ConsentPolicy::AcceptAll) {
+            ConsentPolicy::AcceptAll => {}
+            ConsentPolicy::AllowList(contract_ids) => {
+                require!(contract_ids.contains(contract_id), "The account does not accept tokens of this community");
+            }
+            ConsentPolicy::Pending => {
+                let memo_len = memo.map_or(0, str::len);
+                require!(memo_len <= MAX_PENDING_MINT_MEMO_LEN, "The memo of a pending mint is too long");
+                let mut pending_mints = self.pending_mints.get(owner_id).unwrap_or_default();
+                require!(pending_mints.len() < MAX_TOTAL_PENDING_MINTS, "Too many mints are pending for the account");
+                let community_pending_mints = pending_mints.iter().filter(|pending_mint| &pending_mint.contract_id == contract_id).count();
+                require!(community_pending_mints < MAX_PENDING_MINTS, "Too many mints of this community are pending for the account");
+                pending_mints.push(PendingMint { contract_id: contract_id.clone(), amount: amount.into(), memo: memo.map(str::to_string) });
+                self.pending_mints.insert(owner_id, &pending_mints);
+                FtMintPending { owner_id, contract_id, amount: &amount.into(), memo }.emit();
+                return false;
+            }
+        }
+        self.internal_deposit(owner_id, amount, contract_id);
+        FtMint { owner_id, contract_id, amount: &amount.into(), memo }.emit();
+        true
+    }
+
+    /// Panics unless `owner_id` is registered, did not block `contract_id`, and mints of
+    /// `contract_id` are not paused for it.
+    fn internal_assert_can_receive(&self, owner_id: &AccountId, contract_id: &AccountId) {
+        require!(self.accounts.contains_key(owner_id), "The account is not registered");
+        let is_blocked = match self.blocked_communities.get(owner_id) {
+            Some(blocked_communities) => blocked_communities.contains(contract_id),
+            None => false
+        };
+        require!(!is_blocked, "The account blocked the tokens of this community");
+        self.internal_assert_not_paused(PausableOperation::Mint, owner_id, contract_id);
+    }
+
+    /// Removes and returns the pending mints of `contract_id` for `owner_id`.
+    fn internal_take_pending_mints(&mut self, owner_id: &AccountId, contract_id: &AccountId) -> Vec<PendingMint> {
+        let taken = self.internal_remove_pending_mints(owner_id, contract_id);
+        require!(!taken.is_empty(), "No mints of this community are pending");
+        taken
+    }
+
+    /// Like [`internal_take_pending_mints`](Self::internal_take_pending_mints), returning nothing
+    /// if none are pending.
+    pub(crate) fn internal_remove_pending_mints(&mut self, owner_id: &AccountId, contract_id: &AccountId) -> Vec<PendingMint> {
+        let pending_mints = self.pending_mints.get(owner_id).unwrap_or_default();
+        let (taken, kept): (Vec<PendingMint>, Vec<PendingMint>) =
+            pending_mints.into_iter().partition(|pending_mint| &pending_mint.contract_id == contract_id);
+        if taken.is_empty() {
+            return taken;
+        }
+        if kept.is_empty() {
+            self.pending_mints.remove(owner_id);
+        } else {
+            self.pending_mints.insert(owner_id, &kept);
+        }
+        taken
+    }
+}
+
+impl FungibleTokenConsent for FungibleToken {
+    fn ft_set_consent_policy(&mut self, policy: ConsentPolicy) {
+        require!(env::attached_deposit() >= 1, "Requires attached deposit of at least 1 yoctoNEAR");
+        let initial_storage_usage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        require!(self.accounts.contains_key(&account_id), "The account is not registered");
+        match policy {
+            ConsentPolicy::AcceptAll => self.consent_policies.remove(&account_id),
+            policy => self.consent_policies.insert(&account_id, &policy),
+        };
+        ConsentPolicyUpdated { account_id: &account_id }.emit();
+        let freed_storage_usage = initial_storage_usage.saturating_sub(env::storage_usage());
+        self.internal_settle_storage(initial_storage_usage);
+        self.internal_refund_storage(&account_id, freed_storage_usage);
+    }
+
+    fn ft_consent_policy(&self, account_id: AccountId) -> ConsentPolicy {
+        self.consent_policies.get(&account_id).unwrap_or(ConsentPolicy::AcceptAll)
+    }
+
+    fn ft_pending_mints(&self, account_id: AccountId) -> Vec<PendingMint> {
+        self.pending_mints.get(&account_id).unwrap_or_default()
+    }
+
+    fn ft_accept_mints(&mut self, contract_id: AccountId) -> U128 {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        self.internal_assert_can_receive(&owner_id, &contract_id);
+        let pending_mints = self.internal_take_pending_mints(&owner_id, &contract_id);
+        let mut accepted: Balance = 0;
+        for pending_mint in pending_mints.iter() {
+            self.internal_deposit(&owner_id, pending_mint.amount.0, &contract_id);
+            accepted += pending_mint.amount.0;
+        }
+        let events: Vec<FtMint> = pending_mints
+            .iter()
+            .map(|pending_mint| FtMint { owner_id: &owner_id, contract_id: &contract_id, amount: &pending_mint.amount, memo: pending_mint.memo.as_deref() })
+            .collect();
+        FtMint::emit_many(&events);
+        accepted.into()
+    }
+
+    fn ft_reject_mints(&mut self, contract_id: AccountId) -> U128 {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        let mut rejected: Balance = 0;
+        for pending_mint in self.internal_take_pending_mints(&owner_id, &contract_id) {
+            FtMintRejected { owner_id: &owner_id, contract_id: &contract_id, amount: &pending_mint.amount, memo: pending_mint.memo.as_deref() }.emit();
+            rejected += pending_mint.amount.0;
+        }
+        rejected.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::core::FungibleTokenCore;
+    use crate::test_utils::{community, registered_token, set_caller};
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::ONE_NEAR;
+
+    fn setup(policy: ConsentPolicy) -> FungibleToken {
+        let mut token = registered_token(accounts(1), ONE_NEAR);
+        token.ft_set_consent_policy(policy);
+        token
+    }
+
+    #[test]
+    fn pending_mints() {
+        let mut token = setup(ConsentPolicy::Pending);
+        assert!(!token.internal_mint(&accounts(1), 100, &community(), Some("welcome")));
+        assert!(get_logs().last().unwrap().contains(r#""event":"ft_mint_pending","data":[{"owner_id":"bob","contract_id":"community","amount":"100","memo":"welcome"}]"#));
+        token.internal_mint(&accounts(1), 50, &community(), None);
+        token.internal_mint(&accounts(1), 10, &accounts(3), None);
+        assert_eq!(token.ft_balance_of(accounts(1), None).0, 0);
+        assert_eq!(token.ft_pending_mints(accounts(1)).len(), 3);
+
+        set_caller(accounts(1), 1);
+        assert_eq!(token.ft_accept_mints(community()).0, 150);
+        let mint_log = get_logs().pop().unwrap();
+        assert!(mint_log.contains(r#""event":"ft_mint""#) && mint_log.contains(r#""amount":"100""#) && mint_log.contains(r#""amount":"50""#));
+        assert_eq!(token.ft_balance_of(accounts(1), Some(community())).0, 150);
+        assert_eq!(token.ft_total_supply(Some(community())).0, 150);
+        assert_eq!(token.ft_reject_mints(accounts(3)).0, 10);
+        assert!(token.ft_pending_mints(accounts(1)).is_empty());
+        assert_eq!(token.ft_total_supply(Some(accounts(3))).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account blocked the tokens of this community")]
+    fn accept_blocked_mints() {
+        let mut token = setup(ConsentPolicy::Pending);
+        token.internal_mint(&accounts(1), 100, &community(), None);
+        token.blocked_communities.insert(&accounts(1), &vec![community()]);
+        set_caller(accounts(1), 1);
+        token.ft_accept_mints(community());
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many mints of this community are pending for the account")]
+    fn too_many_pending_mints() {
+        let mut token = setup(ConsentPolicy::Pending);
+        for _ in 0..MAX_PENDING_MINTS {
+            token.internal_mint(&accounts(1), 1, &community(), None);
+        }
+        token.internal_mint(&accounts(1), 1, &accounts(3), None);
+        assert_eq!(token.ft_pending_mints(accounts(1)).len(), MAX_PENDING_MINTS + 1);
+        token.internal_mint(&accounts(1), 1, &community(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many mints are pending for the account")]
+    fn too_many_pending_mints_in_all() {
+        let mut token = setup(ConsentPolicy::Pending);
+        let pending_mints: Vec<PendingMint> = (0..MAX_TOTAL_PENDING_MINTS)
+            .map(|index| PendingMint { contract_id: format!("community{}", index).parse().unwrap(), amount: U128(1), memo: None })
+            .collect();
+        token.pending_mints.insert(&accounts(1), &pending_mints);
+        token.internal_mint(&accounts(1), 1, &accounts(3), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The memo of a pending mint is too long")]
+    fn pending_mint_memo_too_long() {
+        let mut token = setup(ConsentPolicy::Pending);
+        let memo = "a".repeat(MAX_PENDING_MINT_MEMO_LEN + 1);
+        token.internal_mint(&accounts(1), 1, &community(), Some(&memo));
+    }
+
+    #[test]
+    fn accept_all_refunds_storage() {
+        let mut token = setup(ConsentPolicy::AllowList(vec![community()]));
+        let paid = token.storage_paid.get(&accounts(1)).unwrap();
+        set_caller(accounts(1), 1);
+        token.ft_set_consent_policy(ConsentPolicy::AcceptAll);
+        assert_eq!(token.ft_consent_policy(accounts(1)), ConsentPolicy::AcceptAll);
+        assert!(token.storage_paid.get(&accounts(1)).unwrap() < paid);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of at least 1 yoctoNEAR")]
+    fn set_consent_policy_without_deposit() {
+        let mut token = setup(ConsentPolicy::Pending);
+        set_caller(accounts(1), 0);
+        token.ft_set_consent_policy(ConsentPolicy::AcceptAll);
+    }
+
+    #[test]
+    fn allow_list() {
+        let mut token = setup(ConsentPolicy::AllowList(vec![community()]));
+        assert!(token.internal_mint(&accounts(1), 100, &community(), None));
+        assert_eq!(token.ft_balance_of(accounts(1), Some(community())).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account does not accept tokens of this community")]
+    fn allow_list_rejects_others() {
+        let mut token = setup(ConsentPolicy::AllowList(vec![community()]));
+        token.internal_mint(&accounts(1), 100, &accounts(3), None);
+    }
+}
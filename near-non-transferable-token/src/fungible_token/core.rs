@@ -1,13 +1,13 @@
 
-use near_sdk::Balance;
-use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::ext_contract;
-use near_sdk::json_types::U128;
+use near_sdk::json_types::{U128, U64};
 use near_sdk::AccountId;
 use near_sdk::PromiseOrValue;
-use near_sdk::serde::{Serialize, Deserialize};
 
 
+/// The `_call` methods call the receiver and resolve the call with the unused amount it returns.
+/// Each of them returns the amount the receiver used, that is what it was sent minus what went
+/// back to the owner, as `ft_transfer_call` does in NEP-141.
 #[ext_contract(ext_ft_core)]
 pub trait FungibleTokenCore {
 
@@ -22,14 +22,48 @@ pub trait FungibleTokenCore {
     fn ft_total_balance_of(&self, account_id: AccountId, contract_id: Option<AccountId>) -> U128;
 
         
+    /// Locks `amount` of `contract_id` tokens with `receiver_id` and calls `ft_on_deposit` on it.
+    /// With `expires_at` (block timestamp in nanoseconds) set, the owner can take the deposit
+    /// back through `ft_reclaim_deposit` once it has passed. Returns the amount that stays
+    /// deposited. Requires at least 1 yoctoNEAR attached, and a deposit to cover the added storage.
     fn ft_deposit_call(
         &mut self,
         receiver_id: AccountId,
         contract_id: AccountId,
         amount: U128,
         msg: String,
+        expires_at: Option<U64>,
     ) -> PromiseOrValue<U128>;
 
+    /// Locks `amount` of `contract_id` tokens in the bucket `receiver_id` without calling it.
+    /// The bucket is released by `receiver_id` through `ft_release_deposit`, or by the owner
+    /// through `ft_reclaim_deposit` once `expires_at` has passed. Without `expires_at` the deposit
+    /// expires after the maximum lock duration of `receiver_id`, and fails if it has none.
+    /// Requires at least 1 yoctoNEAR attached, and a deposit to cover the added storage.
+    fn ft_deposit(
+        &mut self,
+        receiver_id: AccountId,
+        contract_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        expires_at: Option<U64>,
+    );
+
+    /// Splits a deposit of `contract_id` tokens across several receivers, calling `ft_on_deposit`
+    /// on each with the matching entry of `amounts`. Every receiver's unused amount is returned
+    /// separately, and the used amount for each receiver is returned in the same order.
+    /// Requires at least 1 yoctoNEAR attached, and a deposit to cover the added storage.
+    fn ft_batch_deposit_call(
+        &mut self,
+        receiver_ids: Vec<AccountId>,
+        contract_id: AccountId,
+        amounts: Vec<U128>,
+        msg: String,
+        expires_at: Option<U64>,
+    ) -> PromiseOrValue<Vec<U128>>;
+
+    /// Unlocks `amount` of the deposit held with `receiver_id` and calls `ft_on_withdraw` on it.
+    /// The unused amount is locked again. Returns the amount that stays withdrawn.
     fn ft_withdraw_call(
         &mut self,
         receiver_id: AccountId,
@@ -38,6 +72,35 @@ pub trait FungibleTokenCore {
         msg: String
     ) -> PromiseOrValue<U128>;
 
+    /// Called by a receiver contract to release `amount` of the deposit `owner_id` holds with it
+    /// back to the owner's available balance, without a call back to the receiver. The owner is
+    /// refunded for the storage this frees, up to what it paid.
+    fn ft_release_deposit(
+        &mut self,
+        owner_id: AccountId,
+        contract_id: AccountId,
+        amount: U128,
+        memo: Option<String>
+    );
+
+    /// Returns an expired deposit held with `deposit_contract_id` to the caller's available balance,
+    /// without the receiver's consent. Returns the reclaimed amount.
+    fn ft_reclaim_deposit(
+        &mut self,
+        deposit_contract_id: AccountId,
+        contract_id: AccountId,
+    ) -> U128;
+
+    /// Burns `amount` of the caller's `contract_id` tokens without calling a receiver.
+    fn ft_burn(
+        &mut self,
+        contract_id: AccountId,
+        amount: U128,
+        memo: Option<String>
+    );
+
+    /// Burns `amount` of the caller's `contract_id` tokens and calls `ft_on_burn` on `receiver_id`.
+    /// The unused amount is refunded. Returns the amount that stays burned.
     fn ft_burn_call(
         &mut self,
         receiver_id: AccountId,
@@ -1,23 +1,25 @@
 
-use std::collections::{HashSet, HashMap};
-use std::hash::Hash;
+use std::collections::HashMap;
 
-use crate::fungible_token::events::{FtMint, FtBurn, FtDeposit, FtWithdraw};
+use crate::fungible_token::events::{AccountRegistered, AccountUnregistered, CommunityCreated, FtBurn, FtBurnRefund, FtDeposit, FtDepositRefund, FtWithdraw, FtWithdrawRefund};
 use crate::fungible_token::receiver::ext_ft_receiver;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet, Vector};
-use near_sdk::json_types::U128;
-use near_sdk::serde::{Serialize, Deserialize};
-use near_sdk::serde_json::json;
+use near_sdk::collections::{LookupMap, UnorderedMap};
+use near_sdk::json_types::{U128, U64};
 use near_sdk::{
-    assert_one_yocto, env, log, require, AccountId, Balance, Gas, IntoStorageKey, PromiseOrValue,
-    PromiseResult, StorageUsage, ext_contract, FunctionError,
+    assert_one_yocto, env, require, AccountId, Balance, Gas, IntoStorageKey, Promise, PromiseOrValue,
+    PromiseResult, StorageUsage,
 };
 
 use crate::fungible_token::core::FungibleTokenCore;
 use crate::fungible_token::resolver::{FungibleTokenResolver, ext_ft_resolver};
 use crate::fungible_token::account::FungibleTokenAccount;
+use crate::fungible_token::deposit_registry::DepositRegistry;
+use crate::fungible_token::metadata::FungibleTokenMetadata;
+use crate::fungible_token::consent::{ConsentPolicy, PendingMint};
+use crate::fungible_token::pause::{PausableOperation, Pause};
+use crate::fungible_token::recovery::{Recovery, RecoveryGuardians, DEFAULT_RECOVERY_DELAY};
 
 
 const GAS_FOR_RESOLVE_BURN: Gas = Gas(5_000_000_000_000);
@@ -33,14 +35,16 @@ const GAS_FOR_FT_WITHDRAW_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_W
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Account {
     pub contract_ids: UnorderedMap<Option<AccountId>, (Balance, Balance)>,    //available,  total
-    pub deposit_map: UnorderedMap<AccountId, HashMap<Option<AccountId>, Balance>>  //key: specific community drip
+    pub deposit_map: UnorderedMap<AccountId, HashMap<Option<AccountId>, Balance>>,  //key: specific community drip
+    pub deposit_expiry_map: LookupMap<(AccountId, AccountId), u64>  //key: (community drip, deposit contract), value: expiry timestamp
 }
 
 impl Account {
     pub fn new(prefix: String) -> Self {
         let mut this = Self {
             contract_ids: UnorderedMap::new(prefix.as_bytes()),
-            deposit_map: UnorderedMap::new((prefix + "deposit").as_bytes())
+            deposit_map: UnorderedMap::new((prefix.clone() + "deposit").as_bytes()),
+            deposit_expiry_map: LookupMap::new((prefix + "deposit_expiry").as_bytes())
         };
         this.contract_ids.insert(&(None as Option<AccountId>), &(0, 0));
         this
@@ -74,12 +78,24 @@ impl FungibleTokenAccount for Account {
         amount
     }
 
+    fn refund(&mut self, contract_id: &AccountId, amount: Balance) {
+        for contract_id  in [Some(contract_id.clone()), None] {
+            let balance = self.contract_ids.get(&contract_id).unwrap_or((0, 0));
+            match balance.0.checked_add(amount) {
+                Some(new_available_balance) if new_available_balance <= balance.1 => {
+                    self.contract_ids.insert(&contract_id, &(new_available_balance, balance.1));
+                },
+                _ => env::panic_str("Balance overflow")
+            }
+        }
+    }
+
     fn contract_deposit(&mut self, contract_id: &AccountId, deposit_contract_id: &AccountId, amount: Balance) {
-        let mut contract = self.deposit_map.get(contract_id).unwrap_or(HashMap::new());
-        for contract_id  in [Some(deposit_contract_id.clone()), None] {
-            let balance = contract.get(&contract_id).unwrap_or(&0).clone();
+        let mut contract = self.deposit_map.get(contract_id).unwrap_or_default();
+        for deposit_contract_id  in [Some(deposit_contract_id.clone()), None] {
+            let balance = contract.get(&deposit_contract_id).copied().unwrap_or(0);
             if let Some(new_balance) = balance.checked_add(amount) {
-                contract.insert(contract_id.clone(), new_balance);
+                contract.insert(deposit_contract_id, new_balance);
             } else {
                 env::panic_str("Balance overflow");
             }
@@ -88,17 +104,27 @@ impl FungibleTokenAccount for Account {
     }
 
     fn contract_withdraw(&mut self, contract_id: &AccountId, deposit_contract_id: &AccountId, amount: Balance) {
-        let mut contract = self.deposit_map.get(contract_id).unwrap_or(HashMap::new());
-        for contract_id  in [Some(contract_id.clone()), None] {
-            let balance = contract.get(&Some(deposit_contract_id.clone())).expect("not enough balance").clone();
-            if let Some(new_balance) = balance.checked_add(amount) {
-                contract.insert(contract_id, new_balance);
-            } else {
-                env::panic_str("Not enough balance");
+        let mut contract = self.deposit_map.get(contract_id).unwrap_or_else(|| env::panic_str("Not enough balance"));
+        for deposit_contract_id  in [Some(deposit_contract_id.clone()), None] {
+            let balance = contract.get(&deposit_contract_id).copied().unwrap_or(0);
+            match balance.checked_sub(amount) {
+                Some(0) => {
+                    contract.remove(&deposit_contract_id);
+                },
+                Some(new_balance) => {
+                    contract.insert(deposit_contract_id, new_balance);
+                },
+                None => env::panic_str("Not enough balance")
             }
-            
         }
-        self.deposit_map.insert(contract_id, &contract);
+        if !contract.contains_key(&Some(deposit_contract_id.clone())) {
+            self.deposit_expiry_map.remove(&(contract_id.clone(), deposit_contract_id.clone()));
+        }
+        if contract.is_empty() {
+            self.deposit_map.remove(contract_id);
+        } else {
+            self.deposit_map.insert(contract_id, &contract);
+        }
     }
 
     fn get_available_balance(&self, contract_id: &Option<AccountId>) -> u128 {
@@ -116,19 +142,16 @@ impl FungibleTokenAccount for Account {
                     None => return 0
                 };
                 match contract.get(deposit_contract_id) {
-                    Some(balance) => balance.clone(),
+                    Some(balance) => *balance,
                     None => 0
                 }
             }, 
             None => {
                 let mut total = 0;
                 for (_, deposit) in self.deposit_map.iter() {
-                    match deposit.get(&None) {
-                        Some(balance) => {
-                            total += balance
-                        },
-                        None => {}
-                    };
+                    if let Some(balance) = deposit.get(&None) {
+                        total += balance
+                    }
                 }
                 total
             },
@@ -136,6 +159,18 @@ impl FungibleTokenAccount for Account {
         
     }
 
+    fn get_deposit_expires_at(&self, contract_id: &AccountId, deposit_contract_id: &AccountId) -> Option<u64> {
+        self.deposit_expiry_map.get(&(contract_id.clone(), deposit_contract_id.clone()))
+    }
+
+    fn set_deposit_expires_at(&mut self, contract_id: &AccountId, deposit_contract_id: &AccountId, expires_at: Option<u64>) {
+        let key = (contract_id.clone(), deposit_contract_id.clone());
+        match expires_at {
+            Some(expires_at) => self.deposit_expiry_map.insert(&key, &expires_at),
+            None => self.deposit_expiry_map.remove(&key)
+        };
+    }
+
     fn get_total_balance(&self, contract_id: &Option<AccountId>) -> u128 {
         match self.contract_ids.get(contract_id) {
             Some(balance) => balance.1,
@@ -150,7 +185,7 @@ impl FungibleTokenAccount for Account {
     fn is_deposit_exist(&self, cotnract_id: &AccountId, deposit_contract_id: &AccountId) -> bool {
         match self.deposit_map.get(cotnract_id) {
             Some(contract) => {
-                contract.get(&Some(deposit_contract_id.clone())).is_some()
+                contract.contains_key(&Some(deposit_contract_id.clone()))
             },
             None => false
         }
@@ -178,6 +213,48 @@ pub struct FungibleToken {
 
     /// The storage size in bytes for one account.
     pub account_storage_usage: StorageUsage,
+
+    /// Reverse index of deposits keyed by the receiving contract.
+    pub deposit_registry: DepositRegistry,
+
+    /// Receiving contract -> longest lock duration in nanoseconds it accepts for a deposit.
+    pub max_lock_durations: LookupMap<AccountId, u64>,
+
+    /// Community -> metadata of its token, set by the community account.
+    pub community_metadata: LookupMap<AccountId, FungibleTokenMetadata>,
+
+    /// Account -> which mints it accepts. Accounts without one accept every mint.
+    pub consent_policies: LookupMap<AccountId, ConsentPolicy>,
+
+    /// Account -> mints waiting for it to accept them.
+    pub pending_mints: LookupMap<AccountId, Vec<PendingMint>>,
+
+    /// Account -> communities whose mints it refuses.
+    pub blocked_communities: LookupMap<AccountId, Vec<AccountId>>,
+
+    /// What is paused for every account and community.
+    pub pause: Pause,
+
+    /// Community -> what is paused for its tokens.
+    pub community_pauses: LookupMap<AccountId, Pause>,
+
+    /// Account -> what is frozen for it.
+    pub account_freezes: LookupMap<AccountId, Pause>,
+
+    /// Account -> guardians it chose to approve its recovery.
+    pub recovery_guardians: LookupMap<AccountId, RecoveryGuardians>,
+
+    /// Account -> its pending recovery.
+    pub recoveries: LookupMap<AccountId, Recovery>,
+
+    /// Account -> account it proposed to merge into.
+    pub merge_proposals: LookupMap<AccountId, AccountId>,
+
+    /// How long in nanoseconds a recovery waits after it is requested before it can be finalized.
+    pub recovery_delay: u64,
+
+    /// Account -> yoctoNEAR it paid for storage through the token's methods, less what was refunded.
+    pub storage_paid: LookupMap<AccountId, Balance>,
 }
 
 impl FungibleToken {
@@ -185,8 +262,30 @@ impl FungibleToken {
     where
         S: IntoStorageKey,
     {
-        let mut this =
-            Self { accounts: LookupMap::new(prefix), total_supply: TotalSupply::new("total_supply".to_string()), account_storage_usage: 0 };
+        Self::from_parts(LookupMap::new(prefix), TotalSupply::new("total_supply".to_string()))
+    }
+
+    /// Builds the token around existing `accounts` and `total_supply`, with the other fields empty.
+    pub(crate) fn from_parts(accounts: LookupMap<AccountId, Account>, total_supply: TotalSupply) -> Self {
+        let mut this = Self {
+            accounts,
+            total_supply,
+            account_storage_usage: 0,
+            deposit_registry: DepositRegistry::new("deposit_registry".to_string()),
+            max_lock_durations: LookupMap::new("max_lock_duration".as_bytes()),
+            community_metadata: LookupMap::new("community_metadata".as_bytes()),
+            consent_policies: LookupMap::new("consent_policies".as_bytes()),
+            pending_mints: LookupMap::new("pending_mints".as_bytes()),
+            blocked_communities: LookupMap::new("blocked_communities".as_bytes()),
+            pause: Pause::default(),
+            community_pauses: LookupMap::new("community_pauses".as_bytes()),
+            account_freezes: LookupMap::new("account_freezes".as_bytes()),
+            recovery_guardians: LookupMap::new("recovery_guardians".as_bytes()),
+            recoveries: LookupMap::new("recoveries".as_bytes()),
+            merge_proposals: LookupMap::new("merge_proposals".as_bytes()),
+            recovery_delay: DEFAULT_RECOVERY_DELAY,
+            storage_paid: LookupMap::new("storage_paid".as_bytes()),
+        };
         this.measure_account_storage_usage();
         this
     }
@@ -200,15 +299,26 @@ impl FungibleToken {
         self.accounts.remove(&tmp_account_id);
     }
 
+    pub fn internal_unwrap_account(&self, account_id: &AccountId) -> Account {
+        self.internal_get_account(account_id)
+            .unwrap_or_else(|| env::panic_str(format!("The account {} is not registered", account_id).as_str()))
+    }
+
+    /// Credits `amount` of `contract_id` tokens to `account_id` without logging `ft_mint`, so the
+    /// balance cannot be rebuilt from the events. Mint through
+    /// [`internal_mint`](Self::internal_mint) instead, which logs it.
     pub fn internal_deposit(&mut self, account_id: &AccountId, amount: Balance, contract_id: &AccountId) {
-        let mut account = self.accounts.get(&account_id).expect(format!("The account {} is not registered", &account_id.to_string()).as_str());
+        let mut account = self.internal_unwrap_migrated_account(account_id);
         account.deposit(contract_id, amount);
         self.accounts.insert(account_id, &account);
+        if self.total_supply.contract_ids.get(&Some(contract_id.clone())).is_none() {
+            CommunityCreated { contract_id }.emit();
+        }
         self.total_supply.deposit(contract_id, amount);
     }
 
     pub fn internal_withdraw(&mut self, account_id: &AccountId, amount: Balance, contract_id: &AccountId) {
-        let mut account = self.accounts.get(&account_id).expect(format!("The account {} is not registered", &account_id.to_string()).as_str());
+        let mut account = self.internal_unwrap_migrated_account(account_id);
         let balance = account.get_available_balance(&Some(contract_id.clone()));
         assert!(balance >= amount, "not enough balance");
         account.withdraw(contract_id, amount);
@@ -217,22 +327,59 @@ impl FungibleToken {
     }
 
     pub fn internal_contract_deposit(&mut self, account_id: &AccountId, amount: Balance, contract_id: &AccountId, deposit_contract_id: &AccountId) {
-        let mut account = self.accounts.get(&account_id).expect(format!("The account {} is not registered", &account_id.to_string()).as_str());
+        let mut account = self.internal_unwrap_migrated_account(account_id);
         let balance = account.get_available_balance(&Some(contract_id.clone()));
         assert!(balance >= amount, "not enough balance");
         account.withdraw(contract_id, amount);
         account.contract_deposit(contract_id, deposit_contract_id, amount);
         self.accounts.insert(account_id, &account);
+        self.deposit_registry.deposit(deposit_contract_id, contract_id, account_id, amount);
+    }
 
+    /// Deposits like [`Self::internal_contract_deposit`] and sets the deposit's expiry.
+    /// The expiry of an existing deposit is extended to the later of the two, and a deposit
+    /// without expiry keeps the whole deposit locked until the receiver releases it.
+    pub fn internal_contract_deposit_with_expiry(&mut self, account_id: &AccountId, amount: Balance, contract_id: &AccountId, deposit_contract_id: &AccountId, expires_at: Option<u64>) {
+        if let Some(expires_at) = expires_at {
+            require!(expires_at > env::block_timestamp(), "The expiry should be in the future");
+        }
+        if let Some(max_lock_duration) = self.max_lock_durations.get(deposit_contract_id) {
+            let expires_at = expires_at.unwrap_or_else(|| env::panic_str("The receiver only accepts deposits with an expiry"));
+            require!(
+                expires_at <= env::block_timestamp().saturating_add(max_lock_duration),
+                "The expiry exceeds the maximum lock duration of the receiver"
+            );
+        }
+        self.internal_lock_deposit(account_id, amount, contract_id, deposit_contract_id, expires_at);
+    }
+
+    /// Deposits like [`Self::internal_contract_deposit_with_expiry`], without checking the expiry
+    /// against the receiver, for tokens that were already locked with it.
+    fn internal_lock_deposit(&mut self, account_id: &AccountId, amount: Balance, contract_id: &AccountId, deposit_contract_id: &AccountId, expires_at: Option<u64>) {
+        let account = self.internal_unwrap_account(account_id);
+        // A top-up can only push the expiry back. Without one, the current expiry is kept, and a
+        // deposit that never expires stays that way.
+        let expires_at = if account.is_deposit_exist(contract_id, deposit_contract_id) {
+            account
+                .get_deposit_expires_at(contract_id, deposit_contract_id)
+                .map(|current| expires_at.map_or(current, |expires_at| current.max(expires_at)))
+        } else {
+            expires_at
+        };
+        self.internal_contract_deposit(account_id, amount, contract_id, deposit_contract_id);
+        let mut account = self.internal_unwrap_migrated_account(account_id);
+        account.set_deposit_expires_at(contract_id, deposit_contract_id, expires_at);
+        self.accounts.insert(account_id, &account);
     }
 
     pub fn internal_contract_withdraw(&mut self, account_id: &AccountId, amount: Balance, contract_id: &AccountId, deposit_contract_id: &AccountId) {
-        let mut account = self.accounts.get(&account_id).expect(format!("The account {} is not registered", &account_id.to_string()).as_str());
+        let mut account = self.internal_unwrap_migrated_account(account_id);
         let deposit_balance = account.get_deposit_balance(&Some(contract_id.clone()), &Some(deposit_contract_id.clone()));
         assert!(deposit_balance >= amount, "not enough balance");
         account.contract_withdraw(contract_id, deposit_contract_id, amount);
-        account.deposit(contract_id, amount);
+        account.refund(contract_id, amount);
         self.accounts.insert(account_id, &account);
+        self.deposit_registry.withdraw(deposit_contract_id, contract_id, account_id, amount);
     }
 
 
@@ -240,8 +387,98 @@ impl FungibleToken {
         if self.accounts.insert(account_id, &Account::new(account_id.to_string())).is_some() {
             env::panic_str("The account is already registered");
         }
+        AccountRegistered { account_id }.emit();
+    }
+
+    /// Moves every community balance, deposit and deposit expiry of `account_id` onto
+    /// `new_account_id`, registering it if needed, and removes `account_id`. Expiries of deposits
+    /// both accounts hold are merged like [`Self::internal_contract_deposit_with_expiry`] does.
+    /// Pending mints, blocked communities and freezes move too, while the consent policy of
+    /// `new_account_id` stays.
+    /// The supply does not change.
+    pub fn internal_move_account(&mut self, account_id: &AccountId, new_account_id: &AccountId) {
+        require!(account_id != new_account_id, "The accounts should be different");
+        let mut account = self.internal_unwrap_migrated_account(account_id);
+        self.internal_migrate_account(new_account_id);
+        let mut new_account = match self.accounts.get(new_account_id) {
+            Some(new_account) => new_account,
+            None => {
+                AccountRegistered { account_id: new_account_id }.emit();
+                Account::new(new_account_id.to_string())
+            }
+        };
+
+        let balances: Vec<(AccountId, (Balance, Balance))> = account
+            .contract_ids
+            .iter()
+            .filter_map(|(contract_id, balance)| contract_id.map(|contract_id| (contract_id, balance)))
+            .collect();
+        for (contract_id, (available, total)) in balances {
+            for contract_id in [Some(contract_id), None] {
+                let balance = new_account.contract_ids.get(&contract_id).unwrap_or((0, 0));
+                match (balance.0.checked_add(available), balance.1.checked_add(total)) {
+                    (Some(new_available_balance), Some(new_total_balance)) => {
+                        new_account.contract_ids.insert(&contract_id, &(new_available_balance, new_total_balance));
+                    },
+                    _ => env::panic_str("Balance overflow")
+                }
+            }
+        }
+
+        let deposits: Vec<(AccountId, HashMap<Option<AccountId>, Balance>)> = account.deposit_map.iter().collect();
+        for (contract_id, deposit) in deposits {
+            for (deposit_contract_id, amount) in deposit {
+                let deposit_contract_id = match deposit_contract_id {
+                    Some(deposit_contract_id) => deposit_contract_id,
+                    None => continue
+                };
+                let expires_at = account.get_deposit_expires_at(&contract_id, &deposit_contract_id);
+                let expires_at = if new_account.is_deposit_exist(&contract_id, &deposit_contract_id) {
+                    match (new_account.get_deposit_expires_at(&contract_id, &deposit_contract_id), expires_at) {
+                        (Some(current), Some(expires_at)) => Some(current.max(expires_at)),
+                        _ => None
+                    }
+                } else {
+                    expires_at
+                };
+                new_account.contract_deposit(&contract_id, &deposit_contract_id, amount);
+                new_account.set_deposit_expires_at(&contract_id, &deposit_contract_id, expires_at);
+                account.set_deposit_expires_at(&contract_id, &deposit_contract_id, None);
+                self.deposit_registry.withdraw(&deposit_contract_id, &contract_id, account_id, amount);
+                self.deposit_registry.deposit(&deposit_contract_id, &contract_id, new_account_id, amount);
+            }
+        }
+
+        if let Some(pending_mints) = self.pending_mints.remove(account_id) {
+            let mut new_pending_mints = self.pending_mints.get(new_account_id).unwrap_or_default();
+            new_pending_mints.extend(pending_mints);
+            self.pending_mints.insert(new_account_id, &new_pending_mints);
+        }
+        self.consent_policies.remove(account_id);
+        if let Some(account_freeze) = self.account_freezes.remove(account_id) {
+            let new_account_freeze = self.account_freezes.get(new_account_id).unwrap_or_default();
+            self.account_freezes.insert(new_account_id, &new_account_freeze.or(&account_freeze));
+        }
+        if let Some(blocked_communities) = self.blocked_communities.remove(account_id) {
+            let mut new_blocked_communities = self.blocked_communities.get(new_account_id).unwrap_or_default();
+            for contract_id in blocked_communities {
+                if !new_blocked_communities.contains(&contract_id) {
+                    new_blocked_communities.push(contract_id);
+                }
+            }
+            self.blocked_communities.insert(new_account_id, &new_blocked_communities);
+        }
+        if let Some(storage_paid) = self.storage_paid.remove(account_id) {
+            let new_storage_paid = self.storage_paid.get(new_account_id).unwrap_or(0);
+            self.storage_paid.insert(new_account_id, &(new_storage_paid + storage_paid));
+        }
+
+        account.contract_ids.clear();
+        account.deposit_map.clear();
+        self.accounts.remove(account_id);
+        self.accounts.insert(new_account_id, &new_account);
+        AccountUnregistered { account_id }.emit();
     }
-    
 }
 
 impl FungibleTokenCore for FungibleToken {
@@ -255,14 +492,14 @@ impl FungibleTokenCore for FungibleToken {
     }
 
     fn ft_balance_of(&self, account_id: AccountId, contract_id: Option<AccountId>) -> U128 {
-        match self.accounts.get(&account_id) {
+        match self.internal_get_account(&account_id) {
             Some(account) => account.get_available_balance(&contract_id).into(),
             None => 0.into()
         }
     }
 
     fn ft_total_balance_of(&self, account_id: AccountId, contract_id: Option<AccountId>) -> U128 {
-        match self.accounts.get(&account_id) {
+        match self.internal_get_account(&account_id) {
             Some(account) => account.get_total_balance(&contract_id).into(),
             None => 0.into()
         }
@@ -274,16 +511,29 @@ impl FungibleTokenCore for FungibleToken {
         contract_id: AccountId,
         amount: U128,
         msg: String,
+        expires_at: Option<U64>,
     ) -> PromiseOrValue<U128> {
-        assert_one_yocto();
+        require!(env::attached_deposit() >= 1, "Requires attached deposit of at least 1 yoctoNEAR");
         require!(env::prepaid_gas() > GAS_FOR_FT_DEPOSIT_CALL, "More gas is required");
+        let initial_storage_usage = env::storage_usage();
 
         let sender_id = env::predecessor_account_id();
-        self.internal_contract_deposit(&sender_id, amount.into(), &contract_id, &receiver_id);
+        self.internal_assert_not_paused(PausableOperation::Deposit, &sender_id, &contract_id);
+        self.internal_contract_deposit_with_expiry(&sender_id, amount.into(), &contract_id, &receiver_id, expires_at.map(|expires_at| expires_at.0));
+        self.internal_settle_storage(initial_storage_usage);
+
+        FtDeposit {
+            owner_id: &sender_id,
+            contract_id: &contract_id,
+            deposit_contract_id: &receiver_id,
+            amount: &amount,
+            memo: None,
+        }
+        .emit();
 
         ext_ft_receiver::ext(receiver_id.clone())
         .with_static_gas(env::prepaid_gas() - GAS_FOR_FT_DEPOSIT_CALL)
-        .ft_on_deposit(sender_id.clone(), contract_id.clone(), amount.into(), msg)
+        .ft_on_deposit(sender_id.clone(), contract_id.clone(), amount, msg)
         .then(
             ext_ft_resolver::ext(env::current_account_id())
                 .with_static_gas(GAS_FOR_RESOLVE_BURN)
@@ -292,6 +542,88 @@ impl FungibleTokenCore for FungibleToken {
         .into()
     }
 
+    fn ft_deposit(
+        &mut self,
+        receiver_id: AccountId,
+        contract_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        expires_at: Option<U64>,
+    ) {
+        require!(env::attached_deposit() >= 1, "Requires attached deposit of at least 1 yoctoNEAR");
+        require!(amount.0 > 0, "The amount should be a positive number");
+        let initial_storage_usage = env::storage_usage();
+        let sender_id = env::predecessor_account_id();
+        self.internal_assert_not_paused(PausableOperation::Deposit, &sender_id, &contract_id);
+        // Nothing is called on the bucket, so it may be an account nobody controls. Without an
+        // expiry the deposit could then never come back to its owner.
+        let expires_at = expires_at
+            .map(|expires_at| expires_at.0)
+            .or_else(|| self.max_lock_durations.get(&receiver_id).map(|max_lock_duration| env::block_timestamp().saturating_add(max_lock_duration)));
+        require!(expires_at.is_some(), "A deposit without a call needs an expiry");
+        self.internal_contract_deposit_with_expiry(&sender_id, amount.0, &contract_id, &receiver_id, expires_at);
+        self.internal_settle_storage(initial_storage_usage);
+
+        FtDeposit {
+            owner_id: &sender_id,
+            contract_id: &contract_id,
+            deposit_contract_id: &receiver_id,
+            amount: &amount,
+            memo: memo.as_deref(),
+        }
+        .emit();
+    }
+
+    fn ft_batch_deposit_call(
+        &mut self,
+        receiver_ids: Vec<AccountId>,
+        contract_id: AccountId,
+        amounts: Vec<U128>,
+        msg: String,
+        expires_at: Option<U64>,
+    ) -> PromiseOrValue<Vec<U128>> {
+        require!(env::attached_deposit() >= 1, "Requires attached deposit of at least 1 yoctoNEAR");
+        require!(!receiver_ids.is_empty(), "At least one receiver is required");
+        require!(receiver_ids.len() == amounts.len(), "The number of receivers and amounts should match");
+        let receiver_count = receiver_ids.len() as u64;
+        let gas_for_batch_deposit_call = Gas(GAS_FOR_FT_DEPOSIT_CALL.0 + GAS_FOR_RESOLVE_DEPOSIT.0 * (receiver_count - 1));
+        require!(env::prepaid_gas() > gas_for_batch_deposit_call, "More gas is required");
+        let initial_storage_usage = env::storage_usage();
+
+        let sender_id = env::predecessor_account_id();
+        self.internal_assert_not_paused(PausableOperation::Deposit, &sender_id, &contract_id);
+        let gas_per_receiver = Gas((env::prepaid_gas() - gas_for_batch_deposit_call).0 / receiver_count);
+        let mut promise: Option<Promise> = None;
+        for (idx, (receiver_id, amount)) in receiver_ids.iter().zip(amounts.iter()).enumerate() {
+            require!(!receiver_ids[..idx].contains(receiver_id), "Duplicate receiver");
+            require!(amount.0 > 0, "The amount should be a positive number");
+            self.internal_contract_deposit_with_expiry(&sender_id, amount.0, &contract_id, receiver_id, expires_at.map(|expires_at| expires_at.0));
+            let on_deposit = ext_ft_receiver::ext(receiver_id.clone())
+                .with_static_gas(gas_per_receiver)
+                .ft_on_deposit(sender_id.clone(), contract_id.clone(), *amount, msg.clone());
+            promise = Some(match promise {
+                Some(promise) => promise.and(on_deposit),
+                None => on_deposit
+            });
+        }
+        self.internal_settle_storage(initial_storage_usage);
+        let events: Vec<FtDeposit> = receiver_ids
+            .iter()
+            .zip(amounts.iter())
+            .map(|(receiver_id, amount)| FtDeposit { owner_id: &sender_id, contract_id: &contract_id, deposit_contract_id: receiver_id, amount, memo: None })
+            .collect();
+        FtDeposit::emit_many(&events);
+
+        promise
+        .unwrap()
+        .then(
+            ext_ft_resolver::ext(env::current_account_id())
+                .with_static_gas(Gas(GAS_FOR_RESOLVE_DEPOSIT.0 * receiver_count))
+                .ft_resolve_batch_deposit(sender_id, receiver_ids, contract_id, amounts),
+        )
+        .into()
+    }
+
     fn ft_withdraw_call(
         &mut self,
         receiver_id: AccountId,
@@ -301,21 +633,115 @@ impl FungibleTokenCore for FungibleToken {
     ) -> PromiseOrValue<U128> {
         assert_one_yocto();
         require!(env::prepaid_gas() > GAS_FOR_FT_WITHDRAW_CALL, "More gas is required");
+        let initial_storage_usage = env::storage_usage();
 
         let sender_id = env::predecessor_account_id();
+        self.internal_assert_not_paused(PausableOperation::Withdraw, &sender_id, &contract_id);
+        // A full withdrawal drops the expiry, which the resolver needs to lock the unused part again.
+        let expires_at = self.internal_unwrap_account(&sender_id).get_deposit_expires_at(&contract_id, &receiver_id);
         self.internal_contract_withdraw(&sender_id, amount.0, &contract_id, &receiver_id);
+        // Refunded by the resolver, since locking the unused part again takes it back.
+        let freed_storage_usage = initial_storage_usage.saturating_sub(env::storage_usage());
+
+        FtWithdraw {
+            owner_id: &sender_id,
+            contract_id: &contract_id,
+            deposit_contract_id: &receiver_id,
+            amount: &amount,
+            memo: None,
+        }
+        .emit();
 
         ext_ft_receiver::ext(receiver_id.clone())
         .with_static_gas(env::prepaid_gas() - GAS_FOR_FT_WITHDRAW_CALL)
-        .ft_on_withdraw(sender_id.clone(), contract_id.clone(), amount.into(), msg)
+        .ft_on_withdraw(sender_id.clone(), contract_id.clone(), amount, msg)
         .then(
             ext_ft_resolver::ext(env::current_account_id())
                 .with_static_gas(GAS_FOR_RESOLVE_BURN)
-                .ft_resolve_withdraw(sender_id, receiver_id, contract_id, amount),
+                .ft_resolve_withdraw(sender_id, receiver_id, contract_id, amount, expires_at.map(U64), U64(freed_storage_usage)),
         )
         .into()
     }
 
+    fn ft_release_deposit(
+        &mut self,
+        owner_id: AccountId,
+        contract_id: AccountId,
+        amount: U128,
+        memo: Option<String>
+    ) {
+        assert_one_yocto();
+        require!(amount.0 > 0, "The amount should be a positive number");
+        let initial_storage_usage = env::storage_usage();
+        let deposit_contract_id = env::predecessor_account_id();
+        self.internal_assert_not_paused(PausableOperation::Withdraw, &owner_id, &contract_id);
+        let account = self.internal_unwrap_account(&owner_id);
+        require!(account.is_deposit_exist(&contract_id, &deposit_contract_id), "No deposit found for the caller");
+        self.internal_contract_withdraw(&owner_id, amount.0, &contract_id, &deposit_contract_id);
+        self.internal_refund_storage(&owner_id, initial_storage_usage.saturating_sub(env::storage_usage()));
+
+        FtWithdraw {
+            owner_id: &owner_id,
+            contract_id: &contract_id,
+            deposit_contract_id: &deposit_contract_id,
+            amount: &amount,
+            memo: memo.as_deref(),
+        }
+        .emit();
+    }
+
+    fn ft_reclaim_deposit(
+        &mut self,
+        deposit_contract_id: AccountId,
+        contract_id: AccountId,
+    ) -> U128 {
+        assert_one_yocto();
+        let initial_storage_usage = env::storage_usage();
+        let owner_id = env::predecessor_account_id();
+        self.internal_assert_not_paused(PausableOperation::Withdraw, &owner_id, &contract_id);
+        let account = self.internal_unwrap_account(&owner_id);
+        let amount = account.get_deposit_balance(&Some(contract_id.clone()), &Some(deposit_contract_id.clone()));
+        require!(amount > 0, "No deposit found");
+        let expires_at = account
+            .get_deposit_expires_at(&contract_id, &deposit_contract_id)
+            .unwrap_or_else(|| env::panic_str("The deposit has no expiry"));
+        require!(env::block_timestamp() >= expires_at, "The deposit has not expired yet");
+        self.internal_contract_withdraw(&owner_id, amount, &contract_id, &deposit_contract_id);
+        self.internal_refund_storage(&owner_id, initial_storage_usage.saturating_sub(env::storage_usage()));
+
+        FtWithdraw {
+            owner_id: &owner_id,
+            contract_id: &contract_id,
+            deposit_contract_id: &deposit_contract_id,
+            amount: &amount.into(),
+            memo: Some("expired"),
+        }
+        .emit();
+        amount.into()
+    }
+
+    fn ft_burn(
+        &mut self,
+        contract_id: AccountId,
+        amount: U128,
+        memo: Option<String>
+    ) {
+        assert_one_yocto();
+        require!(amount.0 > 0, "The amount should be a positive number");
+        let sender_id = env::predecessor_account_id();
+        self.internal_assert_not_paused(PausableOperation::Burn, &sender_id, &contract_id);
+        self.internal_withdraw(&sender_id, amount.0, &contract_id);
+
+        FtBurn {
+            owner_id: &sender_id,
+            contract_id: &contract_id,
+            receiver_id: None,
+            amount: &amount,
+            memo: memo.as_deref(),
+        }
+        .emit();
+    }
+
     fn ft_burn_call(
         &mut self,
         receiver_id: AccountId,
@@ -326,15 +752,25 @@ impl FungibleTokenCore for FungibleToken {
         assert_one_yocto();
         require!(env::prepaid_gas() > GAS_FOR_FT_BURN_CALL, "More gas is required");
         let sender_id = env::predecessor_account_id();
+        self.internal_assert_not_paused(PausableOperation::Burn, &sender_id, &contract_id);
         self.internal_withdraw(&sender_id, amount.into(), &contract_id);
 
+        FtBurn {
+            owner_id: &sender_id,
+            contract_id: &contract_id,
+            receiver_id: Some(&receiver_id),
+            amount: &amount,
+            memo: None,
+        }
+        .emit();
+
         ext_ft_receiver::ext(receiver_id.clone())
         .with_static_gas(env::prepaid_gas() - GAS_FOR_FT_BURN_CALL)
-        .ft_on_burn(sender_id.clone(), contract_id.clone(), amount.into(), msg)
+        .ft_on_burn(sender_id.clone(), contract_id.clone(), amount, msg)
         .then(
             ext_ft_resolver::ext(env::current_account_id())
                 .with_static_gas(GAS_FOR_RESOLVE_BURN)
-                .ft_resolve_burn(sender_id, contract_id, amount),
+                .ft_resolve_burn(sender_id, receiver_id, contract_id, amount),
         )
         .into()
     }
@@ -343,18 +779,50 @@ impl FungibleTokenCore for FungibleToken {
 impl FungibleToken {
 
     fn get_unused_amount(&mut self, amount: u128) -> u128 {
-        let used_amount: Balance = match env::promise_result(0) {
+        self.get_unused_amount_at(0, amount)
+    }
+
+    /// Reads the unused amount the receiver returned for the promise at `result_idx`.
+    /// Clamped to `amount`, so a receiver can never get more than it was sent returned.
+    fn get_unused_amount_at(&mut self, result_idx: u64, amount: u128) -> u128 {
+        let unused_amount: Balance = match env::promise_result(result_idx) {
             PromiseResult::NotReady => env::abort(),
             PromiseResult::Successful(value) => {
                 if let Ok(unused_amount) = near_sdk::serde_json::from_slice::<U128>(&value) {
-                    unused_amount.into()
+                    std::cmp::min(amount, unused_amount.0)
                 } else {
                     amount
                 }
             }
             PromiseResult::Failed => amount,
         };
-        used_amount
+        unused_amount
+    }
+
+    /// Returns `amount` that was taken out of the available balance back to it, without
+    /// counting it as newly minted.
+    pub fn internal_refund(&mut self, account_id: &AccountId, amount: Balance, contract_id: &AccountId) {
+        let mut account = self.internal_unwrap_migrated_account(account_id);
+        account.refund(contract_id, amount);
+        self.accounts.insert(account_id, &account);
+        self.total_supply.refund(contract_id, amount);
+    }
+
+    /// Returns up to `amount` of the deposit with `deposit_contract_id` to the owner, and refunds
+    /// the owner for the storage this frees. The deposit may have shrunk while the call was in
+    /// flight, e.g. released by the receiver. Returns the amount actually returned.
+    fn internal_return_unused_deposit(&mut self, owner_id: &AccountId, amount: Balance, contract_id: &AccountId, deposit_contract_id: &AccountId) -> Balance {
+        let deposit_balance = match self.internal_get_account(owner_id) {
+            Some(account) => account.get_deposit_balance(&Some(contract_id.clone()), &Some(deposit_contract_id.clone())),
+            None => return 0
+        };
+        let amount = std::cmp::min(amount, deposit_balance);
+        if amount > 0 {
+            let initial_storage_usage = env::storage_usage();
+            self.internal_contract_withdraw(owner_id, amount, contract_id, deposit_contract_id);
+            self.internal_refund_storage(owner_id, initial_storage_usage.saturating_sub(env::storage_usage()));
+        }
+        amount
     }
 
     pub fn internal_ft_resolve_deposit(
@@ -365,73 +833,137 @@ impl FungibleToken {
         amount: u128,
     ) -> (u128, u128) {
         let unused_amount: Balance = self.get_unused_amount(amount);
-        if unused_amount > 0 {
-            self.internal_contract_withdraw(owner_id, unused_amount, contract_id, receiver_id);
-            return (amount, unused_amount)
+        let refunded_amount = if unused_amount > 0 {
+            self.internal_return_unused_deposit(owner_id, unused_amount, contract_id, receiver_id)
+        } else {
+            0
+        };
+
+        if refunded_amount > 0 {
+            FtDepositRefund {
+                owner_id,
+                contract_id,
+                deposit_contract_id: receiver_id,
+                amount: &refunded_amount.into(),
+                memo: None,
+            }
+            .emit();
         }
+        (amount, refunded_amount)
+    }
 
-        FtDeposit {
-            owner_id: owner_id,
-            amount: &(amount - unused_amount).into(),
-            memo: Some(&json!({
-                "contract_id": contract_id,
-                "deposit_contract_id": receiver_id
-            }).to_string()),
+    /// Resolves every receiver of an `ft_batch_deposit_call` on its own, returning the unused
+    /// part of each deposit to the owner. Returns the used amount for each receiver.
+    pub fn internal_ft_resolve_batch_deposit(
+        &mut self,
+        owner_id: &AccountId,
+        receiver_ids: &[AccountId],
+        contract_id: &AccountId,
+        amounts: &[u128],
+    ) -> Vec<u128> {
+        let mut refund_logs: Vec<(&AccountId, U128)> = vec![];
+        let mut used_amounts = Vec::with_capacity(amounts.len());
+        for (idx, (receiver_id, amount)) in receiver_ids.iter().zip(amounts.iter()).enumerate() {
+            let unused_amount: Balance = self.get_unused_amount_at(idx as u64, *amount);
+            let refunded_amount = if unused_amount > 0 {
+                self.internal_return_unused_deposit(owner_id, unused_amount, contract_id, receiver_id)
+            } else {
+                0
+            };
+            if refunded_amount > 0 {
+                refund_logs.push((receiver_id, refunded_amount.into()));
+            }
+            used_amounts.push(amount - refunded_amount);
         }
-        .emit();
-        (amount, 0)
+
+        if !refund_logs.is_empty() {
+            let events: Vec<FtDepositRefund> = refund_logs
+                .iter()
+                .map(|(receiver_id, amount)| FtDepositRefund { owner_id, contract_id, deposit_contract_id: receiver_id, amount, memo: None })
+                .collect();
+            FtDepositRefund::emit_many(&events);
+        }
+        used_amounts
     }
 
+    /// Locks the unused part of a withdrawal again, with `expires_at`, the expiry the deposit had
+    /// before the withdrawal, even if the receiver changed the lock durations it accepts since.
+    /// If the owner unregistered or spent the tokens while the call was in flight, only what is
+    /// still available is locked. Nothing is locked once the expiry passed, since the owner could
+    /// reclaim the deposit right away. Then refunds the owner the `freed_storage_usage` of the
+    /// withdrawal, less what locking again took back.
     pub fn internal_ft_resolve_withdraw(
         &mut self,
         owner_id: &AccountId,
         receiver_id: &AccountId,
         contract_id: &AccountId,
         amount: u128,
+        expires_at: Option<u64>,
+        freed_storage_usage: StorageUsage,
     ) -> (u128, u128) {
+        let initial_storage_usage = env::storage_usage();
         let unused_amount: Balance = self.get_unused_amount(amount);
-        if unused_amount > 0 {
-            self.internal_contract_deposit(owner_id, unused_amount, contract_id, receiver_id);
-            return (amount, unused_amount)
-        }
-        FtWithdraw {
-            owner_id: owner_id,
-            amount: &(amount - unused_amount).into(),
-            memo: Some(&json!({
-                "contract_id": contract_id,
-                "deposit_contract_id": receiver_id
-            }).to_string()),
+        let is_expired = matches!(expires_at, Some(expires_at) if expires_at <= env::block_timestamp());
+        let relocked_amount = if unused_amount > 0 && !is_expired {
+            let available_balance = match self.internal_get_account(owner_id) {
+                Some(account) => account.get_available_balance(&Some(contract_id.clone())),
+                None => 0
+            };
+            let relocked_amount = std::cmp::min(unused_amount, available_balance);
+            if relocked_amount > 0 {
+                self.internal_lock_deposit(owner_id, relocked_amount, contract_id, receiver_id, expires_at);
+            }
+            relocked_amount
+        } else {
+            0
+        };
+        let relocked_storage_usage = env::storage_usage().saturating_sub(initial_storage_usage);
+        self.internal_refund_storage(owner_id, freed_storage_usage.saturating_sub(relocked_storage_usage));
+
+        if relocked_amount > 0 {
+            FtWithdrawRefund {
+                owner_id,
+                contract_id,
+                deposit_contract_id: receiver_id,
+                amount: &relocked_amount.into(),
+                memo: None,
+            }
+            .emit();
         }
-        .emit();
-        (amount, 0)
+        (amount, relocked_amount)
     }
 
-    
-
-    /// Internal method that returns the amount of burned tokens in a corner case when the sender
-    /// has deleted (unregistered) their account while the `ft_transfer_call` was still in flight.
-    /// Returns (Used token amount, Burned token amount)
+    /// Internal method that returns the unused tokens to the owner. In a corner case when the
+    /// owner has deleted (unregistered) their account while the `ft_burn_call` was still in
+    /// flight, the unused tokens stay burned.
+    /// Returns (Token amount, Refunded token amount)
     pub fn internal_ft_resolve_burn(
         &mut self,
         owner_id: &AccountId,
+        receiver_id: &AccountId,
         contract_id: &AccountId,
         amount: u128,
     ) -> (u128, u128) {
-        // Get the used amount from the `ft_on_transfer` call result.
+        // Get the unused amount from the `ft_on_burn` call result.
         let unused_amount: Balance = self.get_unused_amount(amount);
-        if unused_amount > 0 {
-            self.internal_deposit(owner_id, unused_amount, contract_id);
-            return (amount, unused_amount)
-        }
-        FtBurn {
-            owner_id: owner_id,
-            amount: &(amount - unused_amount).into(),
-            memo: Some(&json!({
-                "contract_id": contract_id
-            }).to_string()),
+        let refunded_amount = if unused_amount > 0 && self.accounts.contains_key(owner_id) {
+            self.internal_refund(owner_id, unused_amount, contract_id);
+            unused_amount
+        } else {
+            0
+        };
+
+        if refunded_amount > 0 {
+            FtBurnRefund {
+                owner_id,
+                contract_id,
+                receiver_id: Some(receiver_id),
+                amount: &refunded_amount.into(),
+                memo: None,
+            }
+            .emit();
         }
-        .emit();
-        (amount, 0)
+        (amount, refunded_amount)
     }
 }
 
@@ -440,10 +972,12 @@ impl FungibleTokenResolver for FungibleToken {
     fn ft_resolve_burn(
         &mut self,
         owner_id: AccountId,
+        receiver_id: AccountId,
         contract_id: AccountId,
         amount: U128,
     ) -> U128 {
-        self.internal_ft_resolve_burn(&owner_id, &contract_id, amount.0).0.into()
+        let (amount, unused_amount) = self.internal_ft_resolve_burn(&owner_id, &receiver_id, &contract_id, amount.0);
+        (amount - unused_amount).into()
     }
 
     fn ft_resolve_deposit(
@@ -453,7 +987,22 @@ impl FungibleTokenResolver for FungibleToken {
         contract_id:AccountId,
         amount:U128,
     ) -> U128 {
-        self.internal_ft_resolve_deposit(&owner_id, &receiver_id, &contract_id, amount.0).0.into()
+        let (amount, unused_amount) = self.internal_ft_resolve_deposit(&owner_id, &receiver_id, &contract_id, amount.0);
+        (amount - unused_amount).into()
+    }
+
+    fn ft_resolve_batch_deposit(
+        &mut self,
+        owner_id: AccountId,
+        receiver_ids: Vec<AccountId>,
+        contract_id: AccountId,
+        amounts: Vec<U128>,
+    ) -> Vec<U128> {
+        let amounts: Vec<u128> = amounts.into_iter().map(|amount| amount.0).collect();
+        self.internal_ft_resolve_batch_deposit(&owner_id, &receiver_ids, &contract_id, &amounts)
+            .into_iter()
+            .map(U128)
+            .collect()
     }
 
     fn ft_resolve_withdraw(
@@ -462,8 +1011,520 @@ impl FungibleTokenResolver for FungibleToken {
         receiver_id: AccountId,
         contract_id:AccountId,
         amount:U128,
+        expires_at: Option<U64>,
+        freed_storage_usage: U64,
     ) -> U128 {
-        self.internal_ft_resolve_withdraw(&owner_id, &receiver_id, &contract_id, amount.0).0.into()
+        let (amount, unused_amount) = self.internal_ft_resolve_withdraw(&owner_id, &receiver_id, &contract_id, amount.0, expires_at.map(|expires_at| expires_at.0), freed_storage_usage.0);
+        (amount - unused_amount).into()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::deposit_registry::FungibleTokenDepositRegistry;
+    use crate::test_utils::{community, set_caller, set_caller_at};
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig, ONE_NEAR};
+
+    fn setup() -> (VMContextBuilder, FungibleToken) {
+        let mut context = VMContextBuilder::new();
+        context.predecessor_account_id(accounts(0));
+        testing_env!(context.build());
+        let mut token = FungibleToken::new(b"t".to_vec());
+        token.internal_register_account(&accounts(1));
+        token.internal_deposit(&accounts(1), 1000, &community());
+        (context, token)
+    }
+
+    fn set_promise_results(context: &VMContextBuilder, promise_results: Vec<PromiseResult>) {
+        testing_env!(
+            context.build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            promise_results,
+        );
+    }
+
+    fn unused(amount: u128) -> PromiseResult {
+        PromiseResult::Successful(near_sdk::serde_json::to_vec(&U128(amount)).unwrap())
+    }
+
+    fn available(token: &FungibleToken, account_id: AccountId) -> u128 {
+        token.ft_balance_of(account_id, Some(community())).0
+    }
+
+    fn deposited(token: &FungibleToken, account_id: AccountId, deposit_contract_id: AccountId) -> u128 {
+        token.internal_unwrap_account(&account_id).get_deposit_balance(&Some(community()), &Some(deposit_contract_id))
+    }
+
+    #[test]
+    fn contract_withdraw_subtracts_the_deposit() {
+        let (_, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 300, &community(), &accounts(3));
+        token.internal_contract_deposit(&accounts(1), 200, &community(), &accounts(4));
+        token.internal_contract_withdraw(&accounts(1), 100, &community(), &accounts(3));
+        assert_eq!(deposited(&token, accounts(1), accounts(3)), 200);
+        assert_eq!(available(&token, accounts(1)), 600);
+        token.internal_contract_withdraw(&accounts(1), 200, &community(), &accounts(3));
+        let account = token.internal_unwrap_account(&accounts(1));
+        assert!(!account.is_deposit_exist(&community(), &accounts(3)));
+        assert_eq!(account.get_deposit_balance(&Some(community()), &None), 200);
+        assert_eq!(available(&token, accounts(1)), 800);
+    }
+
+    #[test]
+    #[should_panic(expected = "not enough balance")]
+    fn contract_withdraw_more_than_deposited() {
+        let (_, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 300, &community(), &accounts(3));
+        token.internal_contract_withdraw(&accounts(1), 301, &community(), &accounts(3));
+    }
+
+    #[test]
+    fn deposit_call_charges_storage() {
+        let (_, mut token) = setup();
+        set_caller(accounts(1), ONE_NEAR);
+        token.ft_deposit_call(accounts(4), community(), U128(300), String::new(), None);
+        assert_eq!(deposited(&token, accounts(1), accounts(4)), 300);
+        let refund = get_created_receipts().into_iter().flat_map(|receipt| receipt.actions).find_map(|action| match action {
+            VmAction::Transfer { deposit } => Some(deposit),
+            _ => None
+        });
+        assert!(refund.unwrap() < ONE_NEAR);
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"storage_changed""#)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of at least 1 yoctoNEAR")]
+    fn deposit_call_without_deposit() {
+        let (_, mut token) = setup();
+        set_caller(accounts(1), 0);
+        token.ft_deposit_call(accounts(4), community(), U128(300), String::new(), None);
+    }
+
+    #[test]
+    fn withdraw_call_refunds_storage_once_resolved() {
+        let (context, mut token) = setup();
+        set_caller(accounts(1), ONE_NEAR);
+        token.ft_deposit_call(accounts(4), community(), U128(300), String::new(), None);
+        let paid = token.storage_paid.get(&accounts(1)).unwrap();
+        set_caller(accounts(1), 1);
+        let initial_storage_usage = env::storage_usage();
+        let _ = token.ft_withdraw_call(accounts(4), community(), U128(300), String::new());
+        let freed_storage_usage = initial_storage_usage - env::storage_usage();
+        assert_eq!(token.storage_paid.get(&accounts(1)).unwrap(), paid);
+
+        set_promise_results(&context, vec![unused(0)]);
+        token.internal_ft_resolve_withdraw(&accounts(1), &accounts(4), &community(), 300, None, freed_storage_usage);
+        let refund = match get_created_receipts().pop().unwrap().actions[..] {
+            [VmAction::Transfer { deposit }] => deposit,
+            _ => panic!("Expected a storage refund")
+        };
+        assert_eq!(refund, Balance::from(freed_storage_usage) * env::storage_byte_cost());
+        assert_eq!(token.storage_paid.get(&accounts(1)).unwrap(), paid - refund);
     }
 
+    #[test]
+    fn resolve_withdraw_keeps_the_storage_of_the_relock() {
+        let (context, mut token) = setup();
+        token.storage_paid.insert(&accounts(1), &ONE_NEAR);
+        token.internal_contract_deposit(&accounts(1), 100, &community(), &accounts(3));
+        let initial_storage_usage = env::storage_usage();
+        token.internal_contract_withdraw(&accounts(1), 100, &community(), &accounts(3));
+        let freed_storage_usage = initial_storage_usage - env::storage_usage();
+        set_promise_results(&context, vec![unused(100)]);
+        token.internal_ft_resolve_withdraw(&accounts(1), &accounts(3), &community(), 100, None, freed_storage_usage);
+        assert!(get_created_receipts().is_empty());
+        assert_eq!(token.storage_paid.get(&accounts(1)).unwrap(), ONE_NEAR);
+        assert_eq!(deposited(&token, accounts(1), accounts(3)), 100);
+    }
+
+    #[test]
+    fn resolve_withdraw_keeps_the_expiry() {
+        let (context, mut token) = setup();
+        token.internal_contract_deposit_with_expiry(&accounts(1), 100, &community(), &accounts(3), Some(10));
+        token.internal_contract_withdraw(&accounts(1), 100, &community(), &accounts(3));
+        assert_eq!(expires_at(&token, accounts(3)), None);
+        set_promise_results(&context, vec![unused(100)]);
+        assert_eq!(token.internal_ft_resolve_withdraw(&accounts(1), &accounts(3), &community(), 100, Some(10), 0), (100, 100));
+        assert_eq!(deposited(&token, accounts(1), accounts(3)), 100);
+        assert_eq!(expires_at(&token, accounts(3)), Some(10));
+    }
+
+    #[test]
+    fn resolve_withdraw_ignores_a_new_max_lock_duration() {
+        let (context, mut token) = setup();
+        token.internal_contract_deposit_with_expiry(&accounts(1), 100, &community(), &accounts(3), Some(10));
+        token.internal_contract_withdraw(&accounts(1), 100, &community(), &accounts(3));
+        token.max_lock_durations.insert(&accounts(3), &5);
+        set_promise_results(&context, vec![unused(100)]);
+        assert_eq!(token.internal_ft_resolve_withdraw(&accounts(1), &accounts(3), &community(), 100, Some(10), 0), (100, 100));
+        assert_eq!(deposited(&token, accounts(1), accounts(3)), 100);
+        assert_eq!(expires_at(&token, accounts(3)), Some(10));
+    }
+
+    #[test]
+    fn resolve_withdraw_after_expiry() {
+        let (mut context, mut token) = setup();
+        token.internal_contract_deposit_with_expiry(&accounts(1), 100, &community(), &accounts(3), Some(10));
+        token.internal_contract_withdraw(&accounts(1), 100, &community(), &accounts(3));
+        set_promise_results(context.block_timestamp(10), vec![unused(100)]);
+        assert_eq!(token.internal_ft_resolve_withdraw(&accounts(1), &accounts(3), &community(), 100, Some(10), 0), (100, 0));
+        assert_eq!(available(&token, accounts(1)), 1000);
+    }
+
+    #[test]
+    fn release_deposit_refunds_storage() {
+        let (_, mut token) = setup();
+        set_caller(accounts(1), ONE_NEAR);
+        token.ft_deposit_call(accounts(4), community(), U128(300), String::new(), None);
+        let paid = token.storage_paid.get(&accounts(1)).unwrap();
+        set_caller(accounts(4), 1);
+        token.ft_release_deposit(accounts(1), community(), U128(300), None);
+        let receipt = get_created_receipts().pop().unwrap();
+        assert_eq!(receipt.receiver_id, accounts(1));
+        let refund = match receipt.actions[..] {
+            [VmAction::Transfer { deposit }] => deposit,
+            _ => panic!("Expected a storage refund")
+        };
+        assert!(refund > 0);
+        assert_eq!(token.storage_paid.get(&accounts(1)).unwrap(), paid - refund);
+    }
+
+    #[test]
+    fn release_deposit_by_receiver() {
+        let (_, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 300, &community(), &accounts(4));
+        set_caller(accounts(4), 1);
+        token.ft_release_deposit(accounts(1), community(), U128(100), None);
+        assert_eq!(deposited(&token, accounts(1), accounts(4)), 200);
+        assert_eq!(available(&token, accounts(1)), 800);
+        assert!(get_logs()[0].contains(r#""event":"ft_withdraw""#) && get_logs()[0].contains(r#""amount":"100""#));
+    }
+
+    #[test]
+    #[should_panic(expected = "No deposit found for the caller")]
+    fn release_deposit_by_owner() {
+        let (_, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 300, &community(), &accounts(4));
+        set_caller(accounts(1), 1);
+        token.ft_release_deposit(accounts(1), community(), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "No deposit found for the caller")]
+    fn release_deposit_by_other_contract() {
+        let (_, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 300, &community(), &accounts(4));
+        set_caller(accounts(3), 1);
+        token.ft_release_deposit(accounts(1), community(), U128(100), None);
+    }
+
+    fn expires_at(token: &FungibleToken, deposit_contract_id: AccountId) -> Option<u64> {
+        token.internal_unwrap_account(&accounts(1)).get_deposit_expires_at(&community(), &deposit_contract_id)
+    }
+
+    #[test]
+    fn reclaim_deposit_after_expiry() {
+        let (_, mut token) = setup();
+        token.internal_contract_deposit_with_expiry(&accounts(1), 300, &community(), &accounts(4), Some(10));
+        set_caller_at(accounts(1), 1, 10);
+        assert_eq!(token.ft_reclaim_deposit(accounts(4), community()).0, 300);
+        assert_eq!(available(&token, accounts(1)), 1000);
+        assert_eq!(expires_at(&token, accounts(4)), None);
+        assert!(get_logs()[0].contains(r#""event":"ft_withdraw""#) && get_logs()[0].contains("expired"));
+    }
+
+    #[test]
+    #[should_panic(expected = "The deposit has not expired yet")]
+    fn reclaim_deposit_before_expiry() {
+        let (_, mut token) = setup();
+        token.internal_contract_deposit_with_expiry(&accounts(1), 300, &community(), &accounts(4), Some(10));
+        set_caller_at(accounts(1), 1, 9);
+        token.ft_reclaim_deposit(accounts(4), community());
+    }
+
+    #[test]
+    fn top_up_keeps_the_expiry() {
+        let (_, mut token) = setup();
+        token.internal_contract_deposit_with_expiry(&accounts(1), 100, &community(), &accounts(4), Some(10));
+        token.internal_contract_deposit_with_expiry(&accounts(1), 100, &community(), &accounts(4), None);
+        assert_eq!(expires_at(&token, accounts(4)), Some(10));
+        token.internal_contract_deposit_with_expiry(&accounts(1), 100, &community(), &accounts(4), Some(5));
+        assert_eq!(expires_at(&token, accounts(4)), Some(10));
+        token.internal_contract_deposit_with_expiry(&accounts(1), 100, &community(), &accounts(4), Some(20));
+        assert_eq!(expires_at(&token, accounts(4)), Some(20));
+    }
+
+    #[test]
+    fn emptied_deposit_drops_the_expiry() {
+        let (_, mut token) = setup();
+        token.internal_contract_deposit_with_expiry(&accounts(1), 300, &community(), &accounts(4), Some(10));
+        token.internal_contract_withdraw(&accounts(1), 100, &community(), &accounts(4));
+        assert_eq!(expires_at(&token, accounts(4)), Some(10));
+        token.internal_contract_withdraw(&accounts(1), 200, &community(), &accounts(4));
+        assert_eq!(expires_at(&token, accounts(4)), None);
+        token.internal_contract_deposit_with_expiry(&accounts(1), 100, &community(), &accounts(4), None);
+        assert_eq!(expires_at(&token, accounts(4)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the required storage balance")]
+    fn batch_deposit_call_without_storage() {
+        let (_, mut token) = setup();
+        set_caller(accounts(1), 1);
+        token.ft_batch_deposit_call(vec![accounts(3), accounts(4)], community(), vec![U128(100), U128(200)], String::new(), None);
+    }
+
+    #[test]
+    fn burn() {
+        let (_, mut token) = setup();
+        set_caller(accounts(1), 1);
+        token.ft_burn(community(), U128(100), Some("spent".to_string()));
+        assert_eq!(available(&token, accounts(1)), 900);
+        assert_eq!(token.ft_available_supply(Some(community())).0, 900);
+        assert!(get_logs()[0].contains(r#""event":"ft_burn""#) && get_logs()[0].contains(r#""amount":"100""#));
+    }
+
+    #[test]
+    fn deposit_charges_storage() {
+        let (_, mut token) = setup();
+        set_caller(accounts(1), ONE_NEAR);
+        token.ft_deposit(accounts(4), community(), U128(300), None, Some(U64(10)));
+        assert_eq!(deposited(&token, accounts(1), accounts(4)), 300);
+        assert_eq!(available(&token, accounts(1)), 700);
+        assert_eq!(expires_at(&token, accounts(4)), Some(10));
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"ft_deposit""#) && log.contains(r#""amount":"300""#)));
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"storage_changed""#)));
+    }
+
+    #[test]
+    fn deposit_expires_after_the_max_lock_duration() {
+        let (_, mut token) = setup();
+        set_caller_at(accounts(4), ONE_NEAR, 5);
+        token.ft_set_max_lock_duration(Some(U64(100)));
+        set_caller_at(accounts(1), ONE_NEAR, 5);
+        token.ft_deposit(accounts(4), community(), U128(300), None, None);
+        assert_eq!(expires_at(&token, accounts(4)), Some(105));
+    }
+
+    #[test]
+    #[should_panic(expected = "A deposit without a call needs an expiry")]
+    fn deposit_without_expiry() {
+        let (_, mut token) = setup();
+        set_caller(accounts(1), ONE_NEAR);
+        token.ft_deposit(accounts(4), community(), U128(300), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The attached deposit is less than the required storage balance")]
+    fn deposit_without_storage() {
+        let (_, mut token) = setup();
+        set_caller(accounts(1), 1);
+        token.ft_deposit(accounts(4), community(), U128(300), None, Some(U64(10)));
+    }
+
+    #[test]
+    fn resolve_burn_all_used() {
+        let (context, mut token) = setup();
+        token.internal_withdraw(&accounts(1), 100, &community());
+        set_promise_results(&context, vec![unused(0)]);
+        assert_eq!(token.internal_ft_resolve_burn(&accounts(1), &accounts(3), &community(), 100), (100, 0));
+        assert_eq!(available(&token, accounts(1)), 900);
+        assert_eq!(token.ft_available_supply(Some(community())).0, 900);
+        assert!(get_logs().is_empty());
+    }
+
+    #[test]
+    fn resolve_burn_refunds_without_minting() {
+        let (context, mut token) = setup();
+        token.internal_withdraw(&accounts(1), 100, &community());
+        set_promise_results(&context, vec![unused(40)]);
+        assert_eq!(token.internal_ft_resolve_burn(&accounts(1), &accounts(3), &community(), 100), (100, 40));
+        assert_eq!(get_logs().len(), 1);
+        assert!(get_logs()[0].contains(r#""event":"ft_burn_refund""#) && get_logs()[0].contains(r#""amount":"40""#));
+        assert_eq!(available(&token, accounts(1)), 940);
+        assert_eq!(token.ft_total_balance_of(accounts(1), Some(community())).0, 1000);
+        assert_eq!(token.ft_available_supply(Some(community())).0, 940);
+        assert_eq!(token.ft_total_supply(Some(community())).0, 1000);
+    }
+
+    #[test]
+    fn resolve_burn_clamps_unused_amount() {
+        let (context, mut token) = setup();
+        token.internal_withdraw(&accounts(1), 100, &community());
+        set_promise_results(&context, vec![unused(u128::MAX)]);
+        assert_eq!(token.internal_ft_resolve_burn(&accounts(1), &accounts(3), &community(), 100), (100, 100));
+        assert_eq!(available(&token, accounts(1)), 1000);
+        assert_eq!(token.ft_available_supply(Some(community())).0, 1000);
+    }
+
+    #[test]
+    fn resolve_burn_failed_refunds_all() {
+        let (context, mut token) = setup();
+        token.internal_withdraw(&accounts(1), 100, &community());
+        set_promise_results(&context, vec![PromiseResult::Failed]);
+        assert_eq!(token.internal_ft_resolve_burn(&accounts(1), &accounts(3), &community(), 100), (100, 100));
+        assert_eq!(available(&token, accounts(1)), 1000);
+    }
+
+    #[test]
+    fn resolve_burn_unregistered_owner() {
+        let (context, mut token) = setup();
+        token.internal_withdraw(&accounts(1), 100, &community());
+        token.accounts.remove(&accounts(1));
+        set_promise_results(&context, vec![unused(100)]);
+        assert_eq!(token.internal_ft_resolve_burn(&accounts(1), &accounts(3), &community(), 100), (100, 0));
+        assert_eq!(token.ft_available_supply(Some(community())).0, 900);
+        assert!(get_logs().is_empty());
+    }
+
+    #[test]
+    fn resolve_deposit_partial_refund() {
+        let (context, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 100, &community(), &accounts(3));
+        set_promise_results(&context, vec![unused(30)]);
+        assert_eq!(token.internal_ft_resolve_deposit(&accounts(1), &accounts(3), &community(), 100), (100, 30));
+        assert_eq!(available(&token, accounts(1)), 930);
+        assert_eq!(deposited(&token, accounts(1), accounts(3)), 70);
+        assert_eq!(token.ft_total_balance_of(accounts(1), Some(community())).0, 1000);
+        assert_eq!(token.deposit_registry.depositors.get(&(accounts(3), community())).unwrap().get(&accounts(1)), Some(70));
+    }
+
+    #[test]
+    fn resolve_deposit_clamps_unused_amount() {
+        let (context, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 100, &community(), &accounts(3));
+        set_promise_results(&context, vec![unused(500)]);
+        assert_eq!(token.internal_ft_resolve_deposit(&accounts(1), &accounts(3), &community(), 100), (100, 100));
+        assert_eq!(available(&token, accounts(1)), 1000);
+        assert_eq!(deposited(&token, accounts(1), accounts(3)), 0);
+        assert!(token.deposit_registry.depositors.get(&(accounts(3), community())).is_none());
+    }
+
+    #[test]
+    fn resolve_deposit_released_in_flight() {
+        let (context, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 100, &community(), &accounts(3));
+        token.internal_contract_withdraw(&accounts(1), 80, &community(), &accounts(3));
+        set_promise_results(&context, vec![PromiseResult::Failed]);
+        assert_eq!(token.internal_ft_resolve_deposit(&accounts(1), &accounts(3), &community(), 100), (100, 20));
+        assert_eq!(available(&token, accounts(1)), 1000);
+        assert_eq!(get_logs().len(), 1);
+        assert!(get_logs()[0].contains(r#""event":"ft_deposit_refund""#) && get_logs()[0].contains(r#""amount":"20""#));
+    }
+
+    #[test]
+    fn resolve_deposit_unregistered_owner() {
+        let (context, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 100, &community(), &accounts(3));
+        token.accounts.remove(&accounts(1));
+        set_promise_results(&context, vec![unused(100)]);
+        assert_eq!(token.internal_ft_resolve_deposit(&accounts(1), &accounts(3), &community(), 100), (100, 0));
+    }
+
+    #[test]
+    fn resolve_batch_deposit() {
+        let (context, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 100, &community(), &accounts(2));
+        token.internal_contract_deposit(&accounts(1), 200, &community(), &accounts(3));
+        set_promise_results(&context, vec![unused(1000), PromiseResult::Failed]);
+        let used_amounts = token.internal_ft_resolve_batch_deposit(
+            &accounts(1),
+            &[accounts(2), accounts(3)],
+            &community(),
+            &[100, 200],
+        );
+        assert_eq!(used_amounts, vec![0, 0]);
+        assert_eq!(available(&token, accounts(1)), 1000);
+        assert_eq!(get_logs().len(), 1);
+        assert!(get_logs()[0].contains("ft_deposit_refund"));
+
+        token.internal_contract_deposit(&accounts(1), 100, &community(), &accounts(2));
+        token.internal_contract_deposit(&accounts(1), 200, &community(), &accounts(3));
+        set_promise_results(&context, vec![unused(0), unused(50)]);
+        let used_amounts = token.internal_ft_resolve_batch_deposit(
+            &accounts(1),
+            &[accounts(2), accounts(3)],
+            &community(),
+            &[100, 200],
+        );
+        assert_eq!(used_amounts, vec![100, 150]);
+        assert_eq!(available(&token, accounts(1)), 750);
+        assert_eq!(get_logs().len(), 1);
+        assert!(get_logs()[0].contains(r#""event":"ft_deposit_refund""#) && get_logs()[0].contains(r#""amount":"50""#));
+    }
+
+    #[test]
+    fn resolve_batch_deposit_one_failing_receiver() {
+        let (context, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 100, &community(), &accounts(2));
+        token.internal_contract_deposit(&accounts(1), 200, &community(), &accounts(3));
+        set_promise_results(&context, vec![unused(0), PromiseResult::Failed]);
+        let used_amounts = token.internal_ft_resolve_batch_deposit(
+            &accounts(1),
+            &[accounts(2), accounts(3)],
+            &community(),
+            &[100, 200],
+        );
+        assert_eq!(used_amounts, vec![100, 0]);
+        assert_eq!(available(&token, accounts(1)), 900);
+        assert_eq!(deposited(&token, accounts(1), accounts(2)), 100);
+        assert_eq!(deposited(&token, accounts(1), accounts(3)), 0);
+        assert!(token.deposit_registry.depositors.get(&(accounts(3), community())).is_none());
+        assert_eq!(get_logs().len(), 1);
+        assert!(get_logs()[0].contains(r#""event":"ft_deposit_refund""#) && get_logs()[0].contains(r#""amount":"200""#));
+    }
+
+    #[test]
+    fn resolve_deposit_returns_the_used_amount() {
+        let (context, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 100, &community(), &accounts(3));
+        set_promise_results(&context, vec![unused(30)]);
+        assert_eq!(token.ft_resolve_deposit(accounts(1), accounts(3), community(), U128(100)).0, 70);
+    }
+
+    #[test]
+    fn resolve_withdraw_returns_the_used_amount() {
+        let (context, mut token) = setup();
+        set_promise_results(&context, vec![unused(30)]);
+        assert_eq!(token.ft_resolve_withdraw(accounts(1), accounts(3), community(), U128(100), None, U64(0)).0, 70);
+    }
+
+    #[test]
+    fn resolve_withdraw_partial_relock() {
+        let (context, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 100, &community(), &accounts(3));
+        token.internal_contract_withdraw(&accounts(1), 100, &community(), &accounts(3));
+        set_promise_results(&context, vec![unused(60)]);
+        assert_eq!(token.internal_ft_resolve_withdraw(&accounts(1), &accounts(3), &community(), 100, None, 0), (100, 60));
+        assert_eq!(get_logs().len(), 1);
+        assert!(get_logs()[0].contains(r#""event":"ft_withdraw_refund""#) && get_logs()[0].contains(r#""amount":"60""#));
+        assert_eq!(available(&token, accounts(1)), 940);
+        assert_eq!(deposited(&token, accounts(1), accounts(3)), 60);
+    }
+
+    #[test]
+    fn resolve_withdraw_spent_in_flight() {
+        let (context, mut token) = setup();
+        token.internal_contract_deposit(&accounts(1), 100, &community(), &accounts(3));
+        token.internal_contract_withdraw(&accounts(1), 100, &community(), &accounts(3));
+        token.internal_withdraw(&accounts(1), 950, &community());
+        set_promise_results(&context, vec![unused(u128::MAX)]);
+        assert_eq!(token.internal_ft_resolve_withdraw(&accounts(1), &accounts(3), &community(), 100, None, 0), (100, 50));
+        assert_eq!(available(&token, accounts(1)), 0);
+        assert_eq!(deposited(&token, accounts(1), accounts(3)), 50);
+    }
+
+    #[test]
+    fn resolve_withdraw_unregistered_owner() {
+        let (context, mut token) = setup();
+        token.accounts.remove(&accounts(1));
+        set_promise_results(&context, vec![PromiseResult::Failed]);
+        assert_eq!(token.internal_ft_resolve_withdraw(&accounts(1), &accounts(3), &community(), 100, None, 0), (100, 0));
+    }
 }
@@ -0,0 +1,304 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedMap};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, ext_contract, require, AccountId, Balance};
+
+use crate::fungible_token::account::FungibleTokenAccount;
+use crate::fungible_token::core_impl::FungibleToken;
+
+/// Reverse index of `Account::deposit_map`, keyed by `deposit_contract_id`.
+/// Lets a receiving contract list the communities deposited with it and the depositors of each.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DepositRegistry {
+    prefix: String,
+    /// deposit_contract_id -> contract_id -> total deposited
+    pub communities: LookupMap<AccountId, UnorderedMap<AccountId, Balance>>,
+    /// (deposit_contract_id, contract_id) -> owner_id -> deposited
+    pub depositors: LookupMap<(AccountId, AccountId), UnorderedMap<AccountId, Balance>>,
+}
+
+impl DepositRegistry {
+    pub fn new(prefix: String) -> Self {
+        Self {
+            communities: LookupMap::new((prefix.clone() + "communities").as_bytes()),
+            depositors: LookupMap::new((prefix.clone() + "depositors").as_bytes()),
+            prefix,
+        }
+    }
+
+    pub fn deposit(&mut self, deposit_contract_id: &AccountId, contract_id: &AccountId, owner_id: &AccountId, amount: Balance) {
+        let mut communities = self.communities.get(deposit_contract_id).unwrap_or_else(|| {
+            UnorderedMap::new(format!("{}:{}", self.prefix, deposit_contract_id).as_bytes())
+        });
+        let total = communities.get(contract_id).unwrap_or(0);
+        communities.insert(contract_id, &total.checked_add(amount).unwrap_or_else(|| env::panic_str("Balance overflow")));
+        self.communities.insert(deposit_contract_id, &communities);
+
+        let key = (deposit_contract_id.clone(), contract_id.clone());
+        let mut depositors = self.depositors.get(&key).unwrap_or_else(|| {
+            UnorderedMap::new(format!("{}:{}:{}", self.prefix, deposit_contract_id, contract_id).as_bytes())
+        });
+        let balance = depositors.get(owner_id).unwrap_or(0);
+        depositors.insert(owner_id, &balance.checked_add(amount).unwrap_or_else(|| env::panic_str("Balance overflow")));
+        self.depositors.insert(&key, &depositors);
+    }
+
+    /// Sets the amount `owner_id` has deposited with `deposit_contract_id` to `amount`.
+    pub fn set(&mut self, deposit_contract_id: &AccountId, contract_id: &AccountId, owner_id: &AccountId, amount: Balance) {
+        let balance = self
+            .depositors
+            .get(&(deposit_contract_id.clone(), contract_id.clone()))
+            .and_then(|depositors| depositors.get(owner_id))
+            .unwrap_or(0);
+        if amount > balance {
+            self.deposit(deposit_contract_id, contract_id, owner_id, amount - balance);
+        } else if amount < balance {
+            self.withdraw(deposit_contract_id, contract_id, owner_id, balance - amount);
+        }
+    }
+
+    pub fn withdraw(&mut self, deposit_contract_id: &AccountId, contract_id: &AccountId, owner_id: &AccountId, amount: Balance) {
+        let key = (deposit_contract_id.clone(), contract_id.clone());
+        let mut depositors = self.depositors.get(&key).unwrap_or_else(|| env::panic_str("Not enough balance"));
+        match depositors.get(owner_id).unwrap_or(0).checked_sub(amount) {
+            Some(0) => {
+                depositors.remove(owner_id);
+            },
+            Some(balance) => {
+                depositors.insert(owner_id, &balance);
+            },
+            None => env::panic_str("Not enough balance")
+        }
+        if depositors.is_empty() {
+            self.depositors.remove(&key);
+        } else {
+            self.depositors.insert(&key, &depositors);
+        }
+
+        let mut communities = self.communities.get(deposit_contract_id).unwrap_or_else(|| env::panic_str("Not enough balance"));
+        match communities.get(contract_id).unwrap_or(0).checked_sub(amount) {
+            Some(0) => {
+                communities.remove(contract_id);
+            },
+            Some(total) => {
+                communities.insert(contract_id, &total);
+            },
+            None => env::panic_str("Not enough balance")
+        }
+        if communities.is_empty() {
+            self.communities.remove(deposit_contract_id);
+        } else {
+            self.communities.insert(deposit_contract_id, &communities);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CommunityDeposit {
+    pub contract_id: AccountId,
+    pub amount: U128,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Depositor {
+    pub owner_id: AccountId,
+    pub amount: U128,
+}
+
+#[ext_contract(ext_ft_deposit_registry)]
+pub trait FungibleTokenDepositRegistry {
+    /// Returns the amount `owner_id` has deposited with `deposit_contract_id` in the community `contract_id`.
+    fn ft_deposit_of(&self, owner_id: AccountId, contract_id: AccountId, deposit_contract_id: AccountId) -> U128;
+
+    /// Returns the communities that have deposits with `deposit_contract_id`, with the total deposited for each.
+    /// After an upgrade from 0.0.8, only the deposits of accounts already migrated by
+    /// `FungibleToken::internal_migrate_account` are counted.
+    fn ft_deposit_communities(
+        &self,
+        deposit_contract_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<CommunityDeposit>;
+
+    /// Returns the accounts that have deposited `contract_id` tokens with `deposit_contract_id`, with their amounts.
+    /// After an upgrade from 0.0.8, accounts not yet migrated by `FungibleToken::internal_migrate_account`
+    /// are missing.
+    fn ft_depositors(
+        &self,
+        deposit_contract_id: AccountId,
+        contract_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Depositor>;
+
+    /// Returns the block timestamp after which `owner_id` can reclaim the deposit, if it has one.
+    fn ft_deposit_expires_at(&self, owner_id: AccountId, contract_id: AccountId, deposit_contract_id: AccountId) -> Option<U64>;
+
+    /// Returns the longest lock duration, in nanoseconds, that `deposit_contract_id` accepts.
+    fn ft_max_lock_duration(&self, deposit_contract_id: AccountId) -> Option<U64>;
+
+    /// Sets the longest lock duration the caller accepts for deposits made with it. Once set,
+    /// deposits must carry an expiry within that duration. `None` accepts deposits without expiry,
+    /// and refunds the storage it frees. Requires at least 1 yoctoNEAR attached, and a deposit to
+    /// cover the added storage.
+    fn ft_set_max_lock_duration(&mut self, max_lock_duration: Option<U64>);
+}
+
+impl FungibleTokenDepositRegistry for FungibleToken {
+    fn ft_deposit_of(&self, owner_id: AccountId, contract_id: AccountId, deposit_contract_id: AccountId) -> U128 {
+        match self.internal_get_account(&owner_id) {
+            Some(account) => account.get_deposit_balance(&Some(contract_id), &Some(deposit_contract_id)).into(),
+            None => 0.into()
+        }
+    }
+
+    fn ft_deposit_communities(
+        &self,
+        deposit_contract_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<CommunityDeposit> {
+        let communities = match self.deposit_registry.communities.get(&deposit_contract_id) {
+            Some(communities) => communities,
+            None => return vec![]
+        };
+        communities
+            .iter()
+            .skip(from_index.map_or(0, |index| index.0 as usize))
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .map(|(contract_id, amount)| CommunityDeposit { contract_id, amount: amount.into() })
+            .collect()
+    }
+
+    fn ft_depositors(
+        &self,
+        deposit_contract_id: AccountId,
+        contract_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Depositor> {
+        let depositors = match self.deposit_registry.depositors.get(&(deposit_contract_id, contract_id)) {
+            Some(depositors) => depositors,
+            None => return vec![]
+        };
+        depositors
+            .iter()
+            .skip(from_index.map_or(0, |index| index.0 as usize))
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .map(|(owner_id, amount)| Depositor { owner_id, amount: amount.into() })
+            .collect()
+    }
+
+    fn ft_deposit_expires_at(&self, owner_id: AccountId, contract_id: AccountId, deposit_contract_id: AccountId) -> Option<U64> {
+        self.internal_get_account(&owner_id)
+            .and_then(|account| account.get_deposit_expires_at(&contract_id, &deposit_contract_id))
+            .map(U64)
+    }
+
+    fn ft_max_lock_duration(&self, deposit_contract_id: AccountId) -> Option<U64> {
+        self.max_lock_durations.get(&deposit_contract_id).map(U64)
+    }
+
+    fn ft_set_max_lock_duration(&mut self, max_lock_duration: Option<U64>) {
+        require!(env::attached_deposit() >= 1, "Requires attached deposit of at least 1 yoctoNEAR");
+        let initial_storage_usage = env::storage_usage();
+        let deposit_contract_id = env::predecessor_account_id();
+        match max_lock_duration {
+            Some(max_lock_duration) => self.max_lock_durations.insert(&deposit_contract_id, &max_lock_duration.0),
+            None => self.max_lock_durations.remove(&deposit_contract_id)
+        };
+        let freed_storage_usage = initial_storage_usage.saturating_sub(env::storage_usage());
+        self.internal_settle_storage(initial_storage_usage);
+        self.internal_refund_storage(&deposit_contract_id, freed_storage_usage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{community, registered_token, set_caller};
+    use near_sdk::test_utils::accounts;
+    use near_sdk::ONE_NEAR;
+
+    fn setup() -> FungibleToken {
+        let mut token = registered_token(accounts(0), 1);
+        token.internal_register_account(&accounts(2));
+        for owner_id in [accounts(1), accounts(2)] {
+            token.internal_deposit(&owner_id, 100, &community());
+            token.internal_deposit(&owner_id, 100, &accounts(3));
+        }
+        token.internal_contract_deposit(&accounts(1), 10, &community(), &accounts(4));
+        token.internal_contract_deposit(&accounts(2), 20, &community(), &accounts(4));
+        token.internal_contract_deposit(&accounts(1), 30, &accounts(3), &accounts(4));
+        token
+    }
+
+    #[test]
+    fn deposit_communities_pagination() {
+        let token = setup();
+        assert_eq!(
+            token.ft_deposit_communities(accounts(4), None, None),
+            vec![
+                CommunityDeposit { contract_id: community(), amount: 30.into() },
+                CommunityDeposit { contract_id: accounts(3), amount: 30.into() },
+            ]
+        );
+        assert_eq!(
+            token.ft_deposit_communities(accounts(4), Some(1.into()), Some(1)),
+            vec![CommunityDeposit { contract_id: accounts(3), amount: 30.into() }]
+        );
+        assert!(token.ft_deposit_communities(accounts(4), Some(2.into()), None).is_empty());
+        assert!(token.ft_deposit_communities(accounts(5), None, None).is_empty());
+    }
+
+    #[test]
+    fn depositors_pagination() {
+        let token = setup();
+        assert_eq!(
+            token.ft_depositors(accounts(4), community(), Some(0.into()), Some(1)),
+            vec![Depositor { owner_id: accounts(1), amount: 10.into() }]
+        );
+        assert_eq!(
+            token.ft_depositors(accounts(4), community(), Some(1.into()), Some(5)),
+            vec![Depositor { owner_id: accounts(2), amount: 20.into() }]
+        );
+        assert_eq!(token.ft_deposit_of(accounts(2), community(), accounts(4)).0, 20);
+    }
+
+    #[test]
+    fn withdraw_removes_empty_entries() {
+        let mut token = setup();
+        token.internal_contract_withdraw(&accounts(1), 30, &accounts(3), &accounts(4));
+        token.internal_contract_withdraw(&accounts(2), 5, &community(), &accounts(4));
+        assert_eq!(
+            token.ft_deposit_communities(accounts(4), None, None),
+            vec![CommunityDeposit { contract_id: community(), amount: 25.into() }]
+        );
+        assert!(token.ft_depositors(accounts(4), accounts(3), None, None).is_empty());
+        assert!(token.deposit_registry.depositors.get(&(accounts(4), accounts(3))).is_none());
+    }
+
+    #[test]
+    fn clear_max_lock_duration_refunds_storage() {
+        let mut token = setup();
+        set_caller(accounts(4), ONE_NEAR);
+        token.ft_set_max_lock_duration(Some(U64(100)));
+        assert_eq!(token.ft_max_lock_duration(accounts(4)), Some(U64(100)));
+        let paid = token.storage_paid.get(&accounts(4)).unwrap();
+        set_caller(accounts(4), 1);
+        token.ft_set_max_lock_duration(None);
+        assert_eq!(token.ft_max_lock_duration(accounts(4)), None);
+        assert!(token.storage_paid.get(&accounts(4)).unwrap() < paid);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of at least 1 yoctoNEAR")]
+    fn set_max_lock_duration_without_deposit() {
+        let mut token = setup();
+        set_caller(accounts(4), 0);
+        token.ft_set_max_lock_duration(Some(U64(100)));
+    }
+}
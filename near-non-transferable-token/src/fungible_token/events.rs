@@ -1,150 +1,219 @@
-//! Standard for nep141 (Fungible Token) events.
+//! Standard for non-transferable token (`ntt`) events.
 //!
 //! These events will be picked up by the NEAR indexer.
 //!
-//! <https://github.com/near/NEPs/blob/master/specs/Standards/FungibleToken/Event.md>
-//!
 //! This is an extension of the events format (nep-297):
 //! <https://github.com/near/NEPs/blob/master/specs/Standards/EventsFormat.md>
 //!
-//! The three events in this standard are [`FtMint`], [`FtDeposit`], and [`FtBurn`].
+//! Version `1.0.0` of these events was logged under the `nep141` standard, with `contract_id`
+//! and `deposit_contract_id` packed into a JSON string in `memo`. Version `2.0.0` is logged
+//! under its own `ntt` standard and carries them as fields. Enable the `nep141-events`
+//! feature to keep logging the `1.0.0` shape of the events `1.0.0` defined; the others are
+//! logged under `ntt` either way.
+//!
+//! The events in this standard are [`FtMint`], [`FtDeposit`], [`FtWithdraw`], and [`FtBurn`],
+//! plus [`FtDepositRefund`], [`FtWithdrawRefund`], and [`FtBurnRefund`] for the part of a call
+//! the receiver returned. A deposit, withdrawal or burn with a call is logged in full when the
+//! call is made, and the refund is logged by the resolver, so the events replay in the order
+//! the balances changed.
 //!
 //! These events can be logged by calling `.emit()` on them if a single event, or calling
-//! [`FtMint::emit_many`], [`FtDeposit::emit_many`],
-//! or [`FtBurn::emit_many`] respectively.
+//! `emit_many` on the event type. The structs are generated from the list of `ntt_events!`,
+//! which the owned types of the `replay` feature are generated from as well.
 
+use crate::access_control::timelock::AdminAction;
 use crate::event::NearEvent;
-use near_sdk::json_types::U128;
+use crate::fungible_token::pause::Pause;
+use near_sdk::json_types::{U128, U64};
 use near_sdk::AccountId;
 use serde::Serialize;
 
-/// Data to log for an FT mint event. To log this event, call [`.emit()`](FtMint::emit).
-#[must_use]
-#[derive(Serialize, Debug, Clone)]
-pub struct FtMint<'a> {
-    pub owner_id: &'a AccountId,
-    pub amount: &'a U128,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub memo: Option<&'a str>,
-}
+/// Implements `emit` and `emit_many` for an event logged through the [`NttEventKind`] variant
+/// of the same name.
+macro_rules! impl_ntt_event {
+    ($event:ident) => {
+        impl $event<'_> {
+            /// Logs the event to the host. This is required to ensure that the event is triggered
+            /// and to consume the event.
+            pub fn emit(self) {
+                Self::emit_many(&[self])
+            }
 
-impl FtMint<'_> {
-    /// Logs the event to the host. This is required to ensure that the event is triggered
-    /// and to consume the event.
-    pub fn emit(self) {
-        Self::emit_many(&[self])
-    }
+            /// Logs every event of `data` as one entry, through [`env::log_str`](near_sdk::env::log_str).
+            pub fn emit_many(data: &[$event<'_>]) {
+                emit(NttEventKind::$event(data))
+            }
+        }
+    };
+}
 
-    /// Emits an FT mint event, through [`env::log_str`](near_sdk::env::log_str),
-    /// where each [`FtMint`] represents the data of each mint.
-    pub fn emit_many(data: &[FtMint<'_>]) {
-        new_141_v1(Nep141EventKind::FtMint(data)).emit()
-    }
+/// The borrowed type the contract logs for each field kind of [`ntt_events!`].
+macro_rules! ntt_field {
+    ($lt:lifetime, account) => { &$lt AccountId };
+    ($lt:lifetime, option_account) => { Option<&$lt AccountId> };
+    ($lt:lifetime, accounts) => { &$lt [AccountId] };
+    ($lt:lifetime, amount) => { &$lt U128 };
+    ($lt:lifetime, id) => { &$lt U64 };
+    ($lt:lifetime, str) => { &$lt str };
+    ($lt:lifetime, option_str) => { Option<&$lt str> };
+    ($lt:lifetime, bool) => { bool };
+    ($lt:lifetime, u32) => { u32 };
+    ($lt:lifetime, pause) => { &$lt Pause };
+    ($lt:lifetime, admin_action) => { &$lt AdminAction };
 }
 
-/// Data to log for an FT deposit event. To log this event,
-/// call [`.emit()`](FtDeposit::emit).
-#[must_use]
-#[derive(Serialize, Debug, Clone)]
-pub struct FtDeposit<'a> {
-    pub owner_id: &'a AccountId,
-    pub amount: &'a U128,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub memo: Option<&'a str>,
+/// Generates a struct per event, logged through the [`NttEventKind`] variant of the same name.
+macro_rules! ntt_event_structs {
+    ($(
+        $(#[$meta:meta])*
+        $event:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field:ident: $kind:ident,
+            )*
+        }
+    )*) => {
+        $(
+            $(#[$meta])*
+            #[must_use]
+            #[derive(Serialize, Debug, Clone)]
+            pub struct $event<'a> {
+                $(
+                    $(#[$field_meta])*
+                    pub $field: ntt_field!('a, $kind),
+                )*
+            }
+
+            impl_ntt_event!($event);
+        )*
+
+        #[derive(Serialize, Debug)]
+        #[serde(tag = "event", content = "data")]
+        #[serde(rename_all = "snake_case")]
+        #[allow(clippy::enum_variant_names)]
+        enum NttEventKind<'a> {
+            $($event(&'a [$event<'a>]),)*
+        }
+    };
 }
 
-impl FtDeposit<'_> {
-    /// Logs the event to the host. This is required to ensure that the event is triggered
-    /// and to consume the event.
-    pub fn emit(self) {
-        Self::emit_many(&[self])
-    }
+ntt_events!(ntt_event_structs);
 
-    /// Emits an FT transfer event, through [`env::log_str`](near_sdk::env::log_str),
-    /// where each [`FtDeposit`] represents the data of each transfer.
-    pub fn emit_many(data: &[FtDeposit<'_>]) {
-        new_141_v1(Nep141EventKind::FtDeposit(data)).emit()
-    }
+#[derive(Serialize, Debug)]
+pub(crate) struct NttEvent<'a> {
+    version: &'static str,
+    #[serde(flatten)]
+    event_kind: NttEventKind<'a>,
 }
 
-/// Data to log for an FT withdraw event. To log this event,
-/// call [`.emit()`](FtWithdraw::emit).
-#[must_use]
-#[derive(Serialize, Debug, Clone)]
-pub struct FtWithdraw<'a> {
-    pub owner_id: &'a AccountId,
-    pub amount: &'a U128,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub memo: Option<&'a str>,
+fn new_ntt<'a>(version: &'static str, event_kind: NttEventKind<'a>) -> NearEvent<'a> {
+    NearEvent::Ntt(NttEvent { version, event_kind })
 }
 
-impl FtWithdraw<'_> {
-    /// Logs the event to the host. This is required to ensure that the event is triggered
-    /// and to consume the event.
-    pub fn emit(self) {
-        Self::emit_many(&[self])
-    }
+fn new_ntt_v2(event_kind: NttEventKind) -> NearEvent {
+    new_ntt("2.0.0", event_kind)
+}
 
-    /// Emits an FT transfer event, through [`env::log_str`](near_sdk::env::log_str),
-    /// where each [`FtWithdraw`] represents the data of each transfer.
-    pub fn emit_many(data: &[FtWithdraw<'_>]) {
-        new_141_v1(Nep141EventKind::FtWithdraw(data)).emit()
-    }
+#[cfg(not(feature = "nep141-events"))]
+fn emit(event_kind: NttEventKind) {
+    new_ntt_v2(event_kind).emit()
 }
 
-/// Data to log for an FT burn event. To log this event, call [`.emit()`](FtBurn::emit).
-#[must_use]
-#[derive(Serialize, Debug, Clone)]
-pub struct FtBurn<'a> {
-    pub owner_id: &'a AccountId,
-    pub amount: &'a U128,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub memo: Option<&'a str>,
+/// Events without a `1.0.0` shape, the refunds among them, are still logged under `ntt`.
+#[cfg(feature = "nep141-events")]
+fn emit(event_kind: NttEventKind) {
+    match nep141::Nep141EventKind::from_ntt(event_kind) {
+        Ok(event_kind) => nep141::new_141_v1(event_kind).emit(),
+        Err(event_kind) => new_ntt_v2(event_kind).emit(),
+    }
 }
 
-impl FtBurn<'_> {
-    /// Logs the event to the host. This is required to ensure that the event is triggered
-    /// and to consume the event.
-    pub fn emit(self) {
-        Self::emit_many(&[self])
+/// The `1.0.0` shape of the events, logged under the `nep141` standard.
+#[cfg(feature = "nep141-events")]
+pub(crate) mod nep141 {
+    use super::NttEventKind;
+    use crate::event::NearEvent;
+    use near_sdk::json_types::U128;
+    use near_sdk::serde_json::{Map, Value};
+    use near_sdk::AccountId;
+    use serde::Serialize;
+
+    #[derive(Serialize, Debug)]
+    pub(crate) struct Nep141EventData<'a> {
+        owner_id: &'a AccountId,
+        amount: &'a U128,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        memo: Option<String>,
     }
 
-    /// Emits an FT burn event, through [`env::log_str`](near_sdk::env::log_str),
-    /// where each [`FtBurn`] represents the data of each burn.
-    pub fn emit_many<'a>(data: &'a [FtBurn<'a>]) {
-        new_141_v1(Nep141EventKind::FtBurn(data)).emit()
+    #[derive(Serialize, Debug)]
+    pub(crate) struct Nep141Event<'a> {
+        version: &'static str,
+        #[serde(flatten)]
+        event_kind: Nep141EventKind<'a>,
     }
-}
 
-#[derive(Serialize, Debug)]
-pub(crate) struct Nep141Event<'a> {
-    version: &'static str,
-    #[serde(flatten)]
-    event_kind: Nep141EventKind<'a>,
-}
+    #[derive(Serialize, Debug)]
+    #[serde(tag = "event", content = "data")]
+    #[serde(rename_all = "snake_case")]
+    #[allow(clippy::enum_variant_names)]
+    pub(crate) enum Nep141EventKind<'a> {
+        FtMint(Vec<Nep141EventData<'a>>),
+        FtDeposit(Vec<Nep141EventData<'a>>),
+        FtWithdraw(Vec<Nep141EventData<'a>>),
+        FtBurn(Vec<Nep141EventData<'a>>),
+    }
 
-#[derive(Serialize, Debug)]
-#[serde(tag = "event", content = "data")]
-#[serde(rename_all = "snake_case")]
-#[allow(clippy::enum_variant_names)]
-enum Nep141EventKind<'a> {
-    FtMint(&'a [FtMint<'a>]),
-    FtDeposit(&'a [FtDeposit<'a>]),
-    FtWithdraw(&'a [FtWithdraw<'a>]),
-    FtBurn(&'a [FtBurn<'a>]),
-}
+    /// Packs the fields that `1.0.0` did not have into a JSON string in `memo`.
+    fn data<'a>(owner_id: &'a AccountId, amount: &'a U128, fields: &[(&str, Option<&str>)]) -> Nep141EventData<'a> {
+        let memo: Map<String, Value> = fields
+            .iter()
+            .filter_map(|(key, value)| value.map(|value| (key.to_string(), Value::from(value))))
+            .collect();
+        Nep141EventData { owner_id, amount, memo: Some(Value::Object(memo).to_string()) }
+    }
 
-fn new_141<'a>(version: &'static str, event_kind: Nep141EventKind<'a>) -> NearEvent<'a> {
-    NearEvent::Nep141(Nep141Event { version, event_kind })
-}
+    impl<'a> Nep141EventKind<'a> {
+        /// Converts the events that `1.0.0` had, and hands back the others.
+        pub(super) fn from_ntt(event_kind: NttEventKind<'a>) -> Result<Self, NttEventKind<'a>> {
+            Ok(match event_kind {
+                NttEventKind::FtMint(events) => Self::FtMint(events.iter().map(|e| data(e.owner_id, e.amount, &[
+                    ("contract_id", Some(e.contract_id.as_str())),
+                    ("memo", e.memo),
+                ])).collect()),
+                NttEventKind::FtDeposit(events) => Self::FtDeposit(events.iter().map(|e| data(e.owner_id, e.amount, &[
+                    ("contract_id", Some(e.contract_id.as_str())),
+                    ("deposit_contract_id", Some(e.deposit_contract_id.as_str())),
+                    ("memo", e.memo),
+                ])).collect()),
+                NttEventKind::FtWithdraw(events) => Self::FtWithdraw(events.iter().map(|e| data(e.owner_id, e.amount, &[
+                    ("contract_id", Some(e.contract_id.as_str())),
+                    ("deposit_contract_id", Some(e.deposit_contract_id.as_str())),
+                    ("memo", e.memo),
+                ])).collect()),
+                NttEventKind::FtBurn(events) => Self::FtBurn(events.iter().map(|e| data(e.owner_id, e.amount, &[
+                    ("contract_id", Some(e.contract_id.as_str())),
+                    ("receiver_id", e.receiver_id.map(|receiver_id| receiver_id.as_str())),
+                    ("memo", e.memo),
+                ])).collect()),
+                event_kind => return Err(event_kind),
+            })
+        }
+    }
 
-fn new_141_v1(event_kind: Nep141EventKind) -> NearEvent {
-    new_141("1.0.0", event_kind)
+    fn new_141<'a>(version: &'static str, event_kind: Nep141EventKind<'a>) -> NearEvent<'a> {
+        NearEvent::Nep141(Nep141Event { version, event_kind })
+    }
+
+    pub(crate) fn new_141_v1(event_kind: Nep141EventKind) -> NearEvent {
+        new_141("1.0.0", event_kind)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::community;
     use near_sdk::{test_utils, AccountId};
 
     fn bob() -> AccountId {
@@ -155,54 +224,250 @@ mod tests {
         AccountId::new_unchecked("alice".to_string())
     }
 
+    fn receiver() -> AccountId {
+        AccountId::new_unchecked("receiver".to_string())
+    }
+
+    #[cfg(not(feature = "nep141-events"))]
     #[test]
     fn ft_mint() {
         let owner_id = &bob();
         let amount = &U128(100);
-        FtMint { owner_id, amount, memo: None }.emit();
+        FtMint { owner_id, contract_id: &community(), amount, memo: None }.emit();
         assert_eq!(
             test_utils::get_logs()[0],
-            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"bob","amount":"100"}]}"#
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_mint","data":[{"owner_id":"bob","contract_id":"community","amount":"100"}]}"#
         );
     }
 
+    #[cfg(not(feature = "nep141-events"))]
     #[test]
     fn ft_mints() {
         let owner_id = &bob();
         let amount = &U128(100);
-        let mint_log = FtMint { owner_id, amount, memo: None };
+        let contract_id = &community();
+        let mint_log = FtMint { owner_id, contract_id, amount, memo: None };
         FtMint::emit_many(&[
             mint_log,
-            FtMint { owner_id: &alice(), amount: &U128(200), memo: Some("has memo") },
+            FtMint { owner_id: &alice(), contract_id, amount: &U128(200), memo: Some("has memo") },
         ]);
         assert_eq!(
             test_utils::get_logs()[0],
-            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"bob","amount":"100"},{"owner_id":"alice","amount":"200","memo":"has memo"}]}"#
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_mint","data":[{"owner_id":"bob","contract_id":"community","amount":"100"},{"owner_id":"alice","contract_id":"community","amount":"200","memo":"has memo"}]}"#
+        );
+    }
+
+    #[cfg(not(feature = "nep141-events"))]
+    #[test]
+    fn ft_deposit() {
+        let owner_id = &bob();
+        let amount = &U128(100);
+        FtDeposit { owner_id, contract_id: &community(), deposit_contract_id: &receiver(), amount, memo: None }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_deposit","data":[{"owner_id":"bob","contract_id":"community","deposit_contract_id":"receiver","amount":"100"}]}"#
         );
     }
 
+    #[cfg(not(feature = "nep141-events"))]
     #[test]
     fn ft_burn() {
         let owner_id = &bob();
         let amount = &U128(100);
-        FtBurn { owner_id, amount, memo: None }.emit();
+        FtBurn { owner_id, contract_id: &community(), receiver_id: None, amount, memo: None }.emit();
         assert_eq!(
             test_utils::get_logs()[0],
-            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"bob","amount":"100"}]}"#
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_burn","data":[{"owner_id":"bob","contract_id":"community","amount":"100"}]}"#
         );
     }
 
+    #[cfg(not(feature = "nep141-events"))]
     #[test]
     fn nft_burns() {
         let owner_id = &bob();
         let amount = &U128(100);
+        let contract_id = &community();
+        FtBurn::emit_many(&[
+            FtBurn { owner_id: &alice(), contract_id, receiver_id: Some(&receiver()), amount: &U128(200), memo: Some("has memo") },
+            FtBurn { owner_id, contract_id, receiver_id: None, amount, memo: None },
+        ]);
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_burn","data":[{"owner_id":"alice","contract_id":"community","receiver_id":"receiver","amount":"200","memo":"has memo"},{"owner_id":"bob","contract_id":"community","amount":"100"}]}"#
+        );
+    }
+
+    #[test]
+    fn ft_deposit_refund() {
+        let owner_id = &bob();
+        let amount = &U128(100);
+        FtDepositRefund { owner_id, contract_id: &community(), deposit_contract_id: &receiver(), amount, memo: None }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_deposit_refund","data":[{"owner_id":"bob","contract_id":"community","deposit_contract_id":"receiver","amount":"100"}]}"#
+        );
+    }
+
+    #[test]
+    fn ft_burn_refunds() {
+        let owner_id = &bob();
+        let amount = &U128(100);
+        let contract_id = &community();
+        FtBurnRefund::emit_many(&[
+            FtBurnRefund { owner_id: &alice(), contract_id, receiver_id: Some(&receiver()), amount: &U128(200), memo: Some("has memo") },
+            FtBurnRefund { owner_id, contract_id, receiver_id: Some(&receiver()), amount, memo: None },
+        ]);
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_burn_refund","data":[{"owner_id":"alice","contract_id":"community","receiver_id":"receiver","amount":"200","memo":"has memo"},{"owner_id":"bob","contract_id":"community","receiver_id":"receiver","amount":"100"}]}"#
+        );
+    }
+
+    #[test]
+    fn ft_slash() {
+        FtSlash { owner_id: &bob(), contract_id: &community(), deposit_contract_id: &receiver(), amount: &U128(100), reason: Some("sybil") }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_slash","data":[{"owner_id":"bob","contract_id":"community","deposit_contract_id":"receiver","amount":"100","reason":"sybil"}]}"#
+        );
+    }
+
+    #[test]
+    fn account_registered() {
+        AccountRegistered { account_id: &bob() }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"account_registered","data":[{"account_id":"bob"}]}"#
+        );
+    }
+
+    #[test]
+    fn account_unregistered() {
+        AccountUnregistered::emit_many(&[AccountUnregistered { account_id: &bob() }, AccountUnregistered { account_id: &alice() }]);
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"account_unregistered","data":[{"account_id":"bob"},{"account_id":"alice"}]}"#
+        );
+    }
+
+    #[test]
+    fn storage_changed() {
+        StorageChanged { account_id: &bob(), amount: &U128(1250000000000000000000), memo: Some("registration") }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"storage_changed","data":[{"account_id":"bob","amount":"1250000000000000000000","memo":"registration"}]}"#
+        );
+    }
+
+    #[test]
+    fn community_created() {
+        CommunityCreated { contract_id: &community() }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"community_created","data":[{"contract_id":"community"}]}"#
+        );
+    }
+
+    #[test]
+    fn role_granted() {
+        RoleGranted { role: "minter", account_id: &bob(), sender_id: &community(), contract_id: None }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"role_granted","data":[{"role":"minter","account_id":"bob","sender_id":"community"}]}"#
+        );
+    }
+
+    #[test]
+    fn role_revoked() {
+        RoleRevoked { role: "minter", account_id: &bob(), sender_id: &alice(), contract_id: Some(&community()) }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"role_revoked","data":[{"role":"minter","account_id":"bob","sender_id":"alice","contract_id":"community"}]}"#
+        );
+    }
+
+    #[test]
+    fn admin_action_approved() {
+        AdminActionApproved { id: &U64(7), sender_id: &alice() }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"admin_action_approved","data":[{"id":"7","sender_id":"alice"}]}"#
+        );
+    }
+
+    #[test]
+    fn metadata_updated() {
+        MetadataUpdated::emit_many(&[
+            MetadataUpdated { sender_id: &bob(), contract_id: None },
+            MetadataUpdated { sender_id: &community(), contract_id: Some(&community()) },
+        ]);
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"metadata_updated","data":[{"sender_id":"bob"},{"sender_id":"community","contract_id":"community"}]}"#
+        );
+    }
+
+    #[test]
+    fn recovery_guardians_updated() {
+        RecoveryGuardiansUpdated { account_id: &bob(), guardians: &[alice(), receiver()], threshold: 2 }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"recovery_guardians_updated","data":[{"account_id":"bob","guardians":["alice","receiver"],"threshold":2}]}"#
+        );
+    }
+
+    #[test]
+    fn recovery_requested() {
+        RecoveryRequested { account_id: &bob(), new_account_id: &alice(), sender_id: &community() }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"recovery_requested","data":[{"account_id":"bob","new_account_id":"alice","sender_id":"community"}]}"#
+        );
+    }
+
+    #[test]
+    fn account_recovered() {
+        AccountRecovered { account_id: &bob(), new_account_id: &alice() }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"account_recovered","data":[{"account_id":"bob","new_account_id":"alice"}]}"#
+        );
+    }
+
+    #[test]
+    fn account_merged() {
+        AccountMerged { account_id: &bob(), new_account_id: &alice() }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"account_merged","data":[{"account_id":"bob","new_account_id":"alice"}]}"#
+        );
+    }
+
+    #[cfg(feature = "nep141-events")]
+    #[test]
+    fn nep141_ft_deposit() {
+        let owner_id = &bob();
+        let amount = &U128(100);
+        FtDeposit { owner_id, contract_id: &community(), deposit_contract_id: &receiver(), amount, memo: None }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_deposit","data":[{"owner_id":"bob","amount":"100","memo":"{\"contract_id\":\"community\",\"deposit_contract_id\":\"receiver\"}"}]}"#
+        );
+    }
+
+    #[cfg(feature = "nep141-events")]
+    #[test]
+    fn nep141_ft_burns() {
+        let owner_id = &bob();
+        let amount = &U128(100);
+        let contract_id = &community();
         FtBurn::emit_many(&[
-            FtBurn { owner_id: &alice(), amount: &U128(200), memo: Some("has memo") },
-            FtBurn { owner_id, amount, memo: None },
+            FtBurn { owner_id: &alice(), contract_id, receiver_id: Some(&receiver()), amount: &U128(200), memo: Some("has memo") },
+            FtBurn { owner_id, contract_id, receiver_id: None, amount, memo: None },
         ]);
         assert_eq!(
             test_utils::get_logs()[0],
-            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"alice","amount":"200","memo":"has memo"},{"owner_id":"bob","amount":"100"}]}"#
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[{"owner_id":"alice","amount":"200","memo":"{\"contract_id\":\"community\",\"memo\":\"has memo\",\"receiver_id\":\"receiver\"}"},{"owner_id":"bob","amount":"100","memo":"{\"contract_id\":\"community\"}"}]}"#
         );
     }
 }
@@ -30,8 +30,33 @@ macro_rules! impl_fungible_token_core {
                 contract_id: AccountId,
                 amount: U128,
                 msg: String,
+                expires_at: Option<U64>,
             ) -> PromiseOrValue<U128> {
-                self.$token.ft_deposit_call(receiver_id, contract_id, amount, msg)
+                self.$token.ft_deposit_call(receiver_id, contract_id, amount, msg, expires_at)
+            }
+
+            #[payable]
+            fn ft_deposit(
+                &mut self,
+                receiver_id: AccountId,
+                contract_id: AccountId,
+                amount: U128,
+                memo: Option<String>,
+                expires_at: Option<U64>,
+            ) {
+                self.$token.ft_deposit(receiver_id, contract_id, amount, memo, expires_at)
+            }
+
+            #[payable]
+            fn ft_batch_deposit_call(
+                &mut self,
+                receiver_ids: Vec<AccountId>,
+                contract_id: AccountId,
+                amounts: Vec<U128>,
+                msg: String,
+                expires_at: Option<U64>,
+            ) -> PromiseOrValue<Vec<U128>> {
+                self.$token.ft_batch_deposit_call(receiver_ids, contract_id, amounts, msg, expires_at)
             }
 
             #[payable]
@@ -45,6 +70,36 @@ macro_rules! impl_fungible_token_core {
                 self.$token.ft_withdraw_call(receiver_id, contract_id, amount, msg)
             }
 
+            #[payable]
+            fn ft_release_deposit(
+                &mut self,
+                owner_id: AccountId,
+                contract_id: AccountId,
+                amount: U128,
+                memo: Option<String>,
+            ) {
+                self.$token.ft_release_deposit(owner_id, contract_id, amount, memo)
+            }
+
+            #[payable]
+            fn ft_reclaim_deposit(
+                &mut self,
+                deposit_contract_id: AccountId,
+                contract_id: AccountId,
+            ) -> U128 {
+                self.$token.ft_reclaim_deposit(deposit_contract_id, contract_id)
+            }
+
+            #[payable]
+            fn ft_burn(
+                &mut self,
+                contract_id: AccountId,
+                amount: U128,
+                memo: Option<String>,
+            ) {
+                self.$token.ft_burn(contract_id, amount, memo)
+            }
+
             #[payable]
             fn ft_burn_call(
                 &mut self,
@@ -71,6 +126,17 @@ macro_rules! impl_fungible_token_core {
                 self.$token.ft_resolve_deposit(owner_id, receiver_id, contract_id, amount)
             }   
             
+            #[private]
+            fn ft_resolve_batch_deposit(
+                &mut self,
+                owner_id: AccountId,
+                receiver_ids: Vec<AccountId>,
+                contract_id: AccountId,
+                amounts: Vec<U128>,
+            ) -> Vec<U128> {
+                self.$token.ft_resolve_batch_deposit(owner_id, receiver_ids, contract_id, amounts)
+            }
+
             #[private]
             fn ft_resolve_withdraw(
                 &mut self,
@@ -78,18 +144,21 @@ macro_rules! impl_fungible_token_core {
                 receiver_id: AccountId,
                 contract_id: AccountId,
                 amount: U128,
+                expires_at: Option<U64>,
+                freed_storage_usage: U64,
             ) -> U128 {
-                self.$token.ft_resolve_withdraw(owner_id, receiver_id, contract_id, amount)
+                self.$token.ft_resolve_withdraw(owner_id, receiver_id, contract_id, amount, expires_at, freed_storage_usage)
             }   
 
             #[private]
             fn ft_resolve_burn(
                 &mut self,
                 owner_id: AccountId,
+                receiver_id: AccountId,
                 contract_id: AccountId,
                 amount: U128,
             ) -> U128 {
-                self.$token.ft_resolve_burn(owner_id, contract_id, amount)
+                self.$token.ft_resolve_burn(owner_id, receiver_id, contract_id, amount)
             }   
         }
     };
@@ -134,3 +203,342 @@ macro_rules! impl_fungible_token_storage {
         }
     };
 }
+
+/// Exposes the reverse index of deposits so receiving contracts can list their depositors.
+#[macro_export]
+macro_rules! impl_fungible_token_deposit_registry {
+    ($contract: ident, $token: ident) => {
+
+        #[near_bindgen]
+        impl FungibleTokenDepositRegistry for $contract {
+
+            fn ft_deposit_of(&self, owner_id: AccountId, contract_id: AccountId, deposit_contract_id: AccountId) -> U128 {
+                self.$token.ft_deposit_of(owner_id, contract_id, deposit_contract_id)
+            }
+
+            fn ft_deposit_communities(
+                &self,
+                deposit_contract_id: AccountId,
+                from_index: Option<U128>,
+                limit: Option<u64>,
+            ) -> Vec<CommunityDeposit> {
+                self.$token.ft_deposit_communities(deposit_contract_id, from_index, limit)
+            }
+
+            fn ft_depositors(
+                &self,
+                deposit_contract_id: AccountId,
+                contract_id: AccountId,
+                from_index: Option<U128>,
+                limit: Option<u64>,
+            ) -> Vec<Depositor> {
+                self.$token.ft_depositors(deposit_contract_id, contract_id, from_index, limit)
+            }
+
+            fn ft_deposit_expires_at(&self, owner_id: AccountId, contract_id: AccountId, deposit_contract_id: AccountId) -> Option<U64> {
+                self.$token.ft_deposit_expires_at(owner_id, contract_id, deposit_contract_id)
+            }
+
+            fn ft_max_lock_duration(&self, deposit_contract_id: AccountId) -> Option<U64> {
+                self.$token.ft_max_lock_duration(deposit_contract_id)
+            }
+
+            #[payable]
+            fn ft_set_max_lock_duration(&mut self, max_lock_duration: Option<U64>) {
+                self.$token.ft_set_max_lock_duration(max_lock_duration)
+            }
+        }
+    };
+}
+
+/// Lets each community set the metadata of its token, read with `ft_community_metadata`.
+#[macro_export]
+macro_rules! impl_fungible_token_community_metadata {
+    ($contract: ident, $token: ident) => {
+
+        #[near_bindgen]
+        impl FungibleTokenCommunityMetadataProvider for $contract {
+
+            fn ft_community_metadata(&self, contract_id: AccountId) -> Option<FungibleTokenMetadata> {
+                self.$token.ft_community_metadata(contract_id)
+            }
+
+            #[payable]
+            fn ft_set_community_metadata(&mut self, metadata: FungibleTokenMetadata) {
+                self.$token.ft_set_community_metadata(metadata)
+            }
+        }
+    };
+}
+
+/// Exposes the metadata of the token, held in a [`FungibleTokenMetadataHolder`](crate::fungible_token::metadata::FungibleTokenMetadataHolder).
+/// Takes name of the Contract struct, the inner field for the metadata and the field of the
+/// account that can update it.
+#[macro_export]
+macro_rules! impl_fungible_token_metadata {
+    ($contract: ident, $metadata: ident, $owner_id: ident) => {
+
+        #[near_bindgen]
+        impl FungibleTokenMetadataProvider for $contract {
+
+            fn ft_metadata(&self) -> FungibleTokenMetadata {
+                self.$metadata.ft_metadata()
+            }
+        }
+
+        #[near_bindgen]
+        impl FungibleTokenMetadataUpdate for $contract {
+
+            #[payable]
+            fn ft_update_metadata(&mut self, metadata: FungibleTokenMetadata) {
+                self.$metadata.update(&self.$owner_id, metadata)
+            }
+        }
+    };
+}
+
+/// Adds the NEP-141 transfer methods, which fail because the token is non-transferable, so that
+/// wallets and explorers show a clear error. The NEP-141 views come with `impl_fungible_token_core`.
+#[macro_export]
+macro_rules! impl_fungible_token_nep141_compat {
+    ($contract: ident, $token: ident) => {
+
+        #[near_bindgen]
+        impl FungibleTokenTransferCompat for $contract {
+
+            #[payable]
+            fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+                self.$token.ft_transfer(receiver_id, amount, memo)
+            }
+
+            #[payable]
+            fn ft_transfer_call(
+                &mut self,
+                receiver_id: AccountId,
+                amount: U128,
+                memo: Option<String>,
+                msg: String,
+            ) -> PromiseOrValue<U128> {
+                self.$token.ft_transfer_call(receiver_id, amount, memo, msg)
+            }
+        }
+    };
+}
+
+/// Exposes the communities as the tokens of a NEP-245 multi token, for tools that read those.
+#[macro_export]
+macro_rules! impl_fungible_token_multi_token_view {
+    ($contract: ident, $token: ident) => {
+
+        #[near_bindgen]
+        impl MultiTokenView for $contract {
+
+            fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128 {
+                self.$token.mt_balance_of(account_id, token_id)
+            }
+
+            fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128> {
+                self.$token.mt_batch_balance_of(account_id, token_ids)
+            }
+
+            fn mt_supply(&self, token_id: TokenId) -> Option<U128> {
+                self.$token.mt_supply(token_id)
+            }
+
+            fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>> {
+                self.$token.mt_batch_supply(token_ids)
+            }
+
+            fn mt_metadata_base_by_token_id(&self, token_ids: Vec<TokenId>) -> Vec<Option<MtBaseTokenMetadata>> {
+                self.$token.mt_metadata_base_by_token_id(token_ids)
+            }
+        }
+    };
+}
+
+/// Lets the guardians or the communities of an account recover it onto a new account, where the
+/// community admins in `$acl` act for their community.
+#[macro_export]
+macro_rules! impl_fungible_token_recovery {
+    ($contract: ident, $token: ident, $acl: ident) => {
+
+        #[near_bindgen]
+        impl FungibleTokenRecovery for $contract {
+
+            #[payable]
+            fn ft_set_recovery_guardians(&mut self, guardians: Vec<AccountId>, threshold: u32) {
+                self.$token.ft_set_recovery_guardians(guardians, threshold)
+            }
+
+            fn ft_recovery_guardians(&self, account_id: AccountId) -> Option<RecoveryGuardians> {
+                self.$token.ft_recovery_guardians(account_id)
+            }
+
+            #[payable]
+            fn ft_request_recovery(&mut self, account_id: AccountId, new_account_id: AccountId) {
+                self.$token.request_recovery(&self.$acl, account_id, new_account_id)
+            }
+
+            #[payable]
+            fn ft_approve_recovery(&mut self, account_id: AccountId) {
+                self.$token.approve_recovery(&self.$acl, account_id)
+            }
+
+            #[payable]
+            fn ft_cancel_recovery(&mut self, account_id: AccountId) {
+                self.$token.ft_cancel_recovery(account_id)
+            }
+
+            #[payable]
+            fn ft_finalize_recovery(&mut self, account_id: AccountId) {
+                self.$token.ft_finalize_recovery(account_id)
+            }
+
+            fn ft_recovery_of(&self, account_id: AccountId) -> Option<Recovery> {
+                self.$token.ft_recovery_of(account_id)
+            }
+        }
+    };
+}
+
+/// Lets an account merge into another account of the same owner, with the consent of both.
+#[macro_export]
+macro_rules! impl_fungible_token_merge {
+    ($contract: ident, $token: ident) => {
+
+        #[near_bindgen]
+        impl FungibleTokenMerge for $contract {
+
+            #[payable]
+            fn ft_propose_merge(&mut self, new_account_id: AccountId) {
+                self.$token.ft_propose_merge(new_account_id)
+            }
+
+            #[payable]
+            fn ft_cancel_merge(&mut self) {
+                self.$token.ft_cancel_merge()
+            }
+
+            #[payable]
+            fn ft_accept_merge(&mut self, account_id: AccountId) {
+                self.$token.ft_accept_merge(account_id)
+            }
+
+            fn ft_merge_proposal_of(&self, account_id: AccountId) -> Option<AccountId> {
+                self.$token.ft_merge_proposal_of(account_id)
+            }
+        }
+    };
+}
+
+/// Lets communities, and their community admins in `$acl`, burn their tokens from any holder, from
+/// the available balance or from deposits.
+#[macro_export]
+macro_rules! impl_fungible_token_clawback {
+    ($contract: ident, $token: ident, $acl: ident) => {
+
+        #[near_bindgen]
+        impl FungibleTokenClawback for $contract {
+
+            #[payable]
+            fn ft_clawback(&mut self, owner_id: AccountId, amount: U128, reason: Option<String>, contract_id: Option<AccountId>) {
+                self.$token.clawback(&self.$acl, owner_id, amount, reason, contract_id)
+            }
+
+            #[payable]
+            fn ft_slash(&mut self, owner_id: AccountId, deposit_contract_id: AccountId, amount: U128, reason: Option<String>, contract_id: Option<AccountId>) {
+                self.$token.slash(&self.$acl, owner_id, deposit_contract_id, amount, reason, contract_id)
+            }
+        }
+    };
+}
+
+/// Lets accounts choose which mints they accept, and accept or reject the pending ones.
+#[macro_export]
+macro_rules! impl_fungible_token_consent {
+    ($contract: ident, $token: ident) => {
+
+        #[near_bindgen]
+        impl FungibleTokenConsent for $contract {
+
+            #[payable]
+            fn ft_set_consent_policy(&mut self, policy: ConsentPolicy) {
+                self.$token.ft_set_consent_policy(policy)
+            }
+
+            fn ft_consent_policy(&self, account_id: AccountId) -> ConsentPolicy {
+                self.$token.ft_consent_policy(account_id)
+            }
+
+            fn ft_pending_mints(&self, account_id: AccountId) -> Vec<PendingMint> {
+                self.$token.ft_pending_mints(account_id)
+            }
+
+            #[payable]
+            fn ft_accept_mints(&mut self, contract_id: AccountId) -> U128 {
+                self.$token.ft_accept_mints(contract_id)
+            }
+
+            #[payable]
+            fn ft_reject_mints(&mut self, contract_id: AccountId) -> U128 {
+                self.$token.ft_reject_mints(contract_id)
+            }
+        }
+    };
+}
+
+/// Lets holders renounce the tokens of a community and block its further mints.
+#[macro_export]
+macro_rules! impl_fungible_token_renounce {
+    ($contract: ident, $token: ident) => {
+
+        #[near_bindgen]
+        impl FungibleTokenRenounce for $contract {
+
+            #[payable]
+            fn ft_renounce(&mut self, contract_id: AccountId, block: Option<bool>) -> U128 {
+                self.$token.ft_renounce(contract_id, block)
+            }
+
+            #[payable]
+            fn ft_unblock_community(&mut self, contract_id: AccountId) {
+                self.$token.ft_unblock_community(contract_id)
+            }
+
+            fn ft_blocked_communities(&self, account_id: AccountId) -> Vec<AccountId> {
+                self.$token.ft_blocked_communities(account_id)
+            }
+        }
+    };
+}
+
+/// Lets the pausers of `acl` pause the token globally or per community, and freeze accounts.
+/// Communities can pause their own tokens too.
+#[macro_export]
+macro_rules! impl_fungible_token_pause {
+    ($contract: ident, $token: ident, $acl: ident) => {
+
+        #[near_bindgen]
+        impl FungibleTokenPause for $contract {
+
+            #[payable]
+            fn ft_set_pause(&mut self, pause: Pause) {
+                self.$token.set_pause(&self.$acl, pause)
+            }
+
+            #[payable]
+            fn ft_set_community_pause(&mut self, contract_id: AccountId, pause: Pause) {
+                self.$token.set_community_pause(&self.$acl, &contract_id, pause)
+            }
+
+            #[payable]
+            fn ft_set_account_freeze(&mut self, account_id: AccountId, pause: Pause) {
+                self.$token.set_account_freeze(&self.$acl, &account_id, pause)
+            }
+
+            fn ft_pause_status(&self, account_id: Option<AccountId>, contract_id: Option<AccountId>) -> Pause {
+                self.$token.pause_status(account_id, contract_id)
+            }
+        }
+    };
+}
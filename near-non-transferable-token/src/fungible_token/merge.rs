@@ -0,0 +1,159 @@
+use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId, Balance, Promise};
+
+use crate::fungible_token::core_impl::FungibleToken;
+use crate::fungible_token::events::{AccountMerged, MergeCancelled, MergeProposed};
+use crate::storage_management::StorageManagement;
+
+/// Merge of two registered accounts of the same owner, such as an implicit and a named account.
+/// The account to merge proposes it, and the account to merge into accepts it. Every community
+/// balance and deposit of the first is then added to the second, the first is unregistered, and
+/// its registration deposit is refunded to it. The supply does not change.
+#[ext_contract(ext_ft_merge)]
+pub trait FungibleTokenMerge {
+    /// Proposes to merge the caller's account into `new_account_id`, replacing any earlier
+    /// proposal. Requires at least 1 yoctoNEAR attached, and a deposit to cover the added storage.
+    fn ft_propose_merge(&mut self, new_account_id: AccountId);
+
+    /// Withdraws the merge the caller proposed. Requires 1 yoctoNEAR attached.
+    fn ft_cancel_merge(&mut self);
+
+    /// Merges `account_id` into the caller's account, if `account_id` proposed it.
+    /// Requires at least 1 yoctoNEAR attached, and a deposit to cover the added storage.
+    fn ft_accept_merge(&mut self, account_id: AccountId);
+
+    fn ft_merge_proposal_of(&self, account_id: AccountId) -> Option<AccountId>;
+}
+
+impl FungibleTokenMerge for FungibleToken {
+    fn ft_propose_merge(&mut self, new_account_id: AccountId) {
+        require!(env::attached_deposit() >= 1, "Requires attached deposit of at least 1 yoctoNEAR");
+        let initial_storage_usage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        require!(self.accounts.contains_key(&account_id), "The account is not registered");
+        require!(self.accounts.contains_key(&new_account_id), "The new account is not registered");
+        require!(account_id != new_account_id, "The accounts should be different");
+        self.merge_proposals.insert(&account_id, &new_account_id);
+        MergeProposed { account_id: &account_id, new_account_id: &new_account_id }.emit();
+        self.internal_settle_storage(initial_storage_usage);
+    }
+
+    fn ft_cancel_merge(&mut self) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let new_account_id = self.merge_proposals.remove(&account_id).unwrap_or_else(|| env::panic_str("No merge is proposed"));
+        MergeCancelled { account_id: &account_id, new_account_id: &new_account_id }.emit();
+    }
+
+    fn ft_accept_merge(&mut self, account_id: AccountId) {
+        require!(env::attached_deposit() >= 1, "Requires attached deposit of at least 1 yoctoNEAR");
+        let initial_storage_usage = env::storage_usage();
+        let new_account_id = env::predecessor_account_id();
+        require!(
+            self.merge_proposals.get(&account_id).as_ref() == Some(&new_account_id),
+            "The account did not propose to merge into the caller"
+        );
+        require!(self.accounts.contains_key(&new_account_id), "The account is not registered");
+        self.merge_proposals.remove(&account_id);
+        self.recoveries.remove(&account_id);
+        self.recovery_guardians.remove(&account_id);
+        self.internal_move_account(&account_id, &new_account_id);
+        AccountMerged { account_id: &account_id, new_account_id: &new_account_id }.emit();
+        let freed_storage_usage = initial_storage_usage.saturating_sub(env::storage_usage());
+        self.internal_settle_storage(initial_storage_usage);
+        // The rest of the freed storage was paid by whoever called the methods that grew it.
+        let refund = (Balance::from(freed_storage_usage) * env::storage_byte_cost()).min(self.storage_balance_bounds().min.0);
+        if refund > 0 {
+            Promise::new(account_id).transfer(refund);
+        }
+    }
+
+    fn ft_merge_proposal_of(&self, account_id: AccountId) -> Option<AccountId> {
+        self.merge_proposals.get(&account_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::core::FungibleTokenCore;
+    use crate::test_utils::{community, registered_token, set_caller};
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs};
+    use near_sdk::ONE_NEAR;
+
+    fn setup() -> FungibleToken {
+        let mut token = registered_token(accounts(1), ONE_NEAR);
+        token.internal_register_account(&accounts(2));
+        token.internal_deposit(&accounts(1), 100, &community());
+        token.internal_deposit(&accounts(1), 50, &accounts(3));
+        token.internal_deposit(&accounts(2), 200, &community());
+        token.internal_contract_deposit(&accounts(1), 30, &community(), &accounts(4));
+        token
+    }
+
+    #[test]
+    fn merge_accounts() {
+        let mut token = setup();
+        token.ft_propose_merge(accounts(2));
+        set_caller(accounts(2), ONE_NEAR);
+        token.ft_accept_merge(accounts(1));
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"account_merged","data":[{"account_id":"bob","new_account_id":"charlie"}]"#)));
+        assert!(!token.accounts.contains_key(&accounts(1)));
+        assert_eq!(token.ft_balance_of(accounts(2), Some(community())).0, 270);
+        assert_eq!(token.ft_total_balance_of(accounts(2), Some(community())).0, 300);
+        assert_eq!(token.ft_total_balance_of(accounts(2), Some(accounts(3))).0, 50);
+        assert_eq!(token.ft_total_balance_of(accounts(2), None).0, 350);
+        assert_eq!(token.deposit_registry.depositors.get(&(accounts(4), community())).unwrap().get(&accounts(2)), Some(30));
+        assert_eq!(token.ft_total_supply(None).0, 350);
+    }
+
+    #[test]
+    fn merge_refunds_at_most_the_registration() {
+        let mut token = setup();
+        token.internal_contract_deposit(&accounts(2), 10, &community(), &accounts(4));
+        for contract_id in (0..5).map(|i| AccountId::new_unchecked(format!("community{}", i))) {
+            token.internal_deposit(&accounts(1), 10, &contract_id);
+            token.internal_deposit(&accounts(2), 10, &contract_id);
+        }
+        token.ft_propose_merge(accounts(2));
+        set_caller(accounts(2), ONE_NEAR);
+        token.ft_accept_merge(accounts(1));
+        let refunded: Balance = get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == accounts(1))
+            .flat_map(|receipt| receipt.actions)
+            .map(|action| match action {
+                VmAction::Transfer { deposit } => deposit,
+                _ => 0
+            })
+            .sum();
+        assert_eq!(refunded, token.storage_balance_bounds().min.0);
+    }
+
+    #[test]
+    fn cancel_merge() {
+        let mut token = setup();
+        token.ft_propose_merge(accounts(2));
+        set_caller(accounts(1), 1);
+        token.ft_cancel_merge();
+        assert!(get_logs()[0].contains(r#""event":"merge_cancelled","data":[{"account_id":"bob","new_account_id":"charlie"}]"#));
+        assert_eq!(token.ft_merge_proposal_of(accounts(1)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of at least 1 yoctoNEAR")]
+    fn accept_merge_without_deposit() {
+        let mut token = setup();
+        token.ft_propose_merge(accounts(2));
+        set_caller(accounts(2), 0);
+        token.ft_accept_merge(accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "The account did not propose to merge into the caller")]
+    fn merge_without_proposal() {
+        let mut token = setup();
+        set_caller(accounts(2), ONE_NEAR);
+        token.ft_accept_merge(accounts(1));
+    }
+}
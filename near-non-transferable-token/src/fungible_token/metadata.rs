@@ -1,7 +1,11 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
 use near_sdk::json_types::Base64VecU8;
-use near_sdk::require;
 use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId, IntoStorageKey};
+
+use crate::fungible_token::core_impl::FungibleToken;
+use crate::fungible_token::events::MetadataUpdated;
 
 pub const FT_METADATA_SPEC: &str = "ft-1.0.0";
 
@@ -21,6 +25,23 @@ pub trait FungibleTokenMetadataProvider {
     fn ft_metadata(&self) -> FungibleTokenMetadata;
 }
 
+#[ext_contract(ext_ft_metadata_update)]
+pub trait FungibleTokenMetadataUpdate {
+    /// Replaces the metadata of the token. Only the owner of the contract can call it,
+    /// with 1 yoctoNEAR attached.
+    fn ft_update_metadata(&mut self, metadata: FungibleTokenMetadata);
+}
+
+#[ext_contract(ext_ft_community_metadata)]
+pub trait FungibleTokenCommunityMetadataProvider {
+    /// Returns the metadata of the token of the community `contract_id`, if it has set one.
+    fn ft_community_metadata(&self, contract_id: AccountId) -> Option<FungibleTokenMetadata>;
+
+    /// Sets the metadata of the token of the caller's community.
+    /// Requires a deposit to cover the added storage.
+    fn ft_set_community_metadata(&mut self, metadata: FungibleTokenMetadata);
+}
+
 impl FungibleTokenMetadata {
     pub fn assert_valid(&self) {
         require!(self.spec == FT_METADATA_SPEC);
@@ -30,3 +51,107 @@ impl FungibleTokenMetadata {
         }
     }
 }
+
+/// Holds the metadata of the token, for [`impl_fungible_token_metadata`](crate::impl_fungible_token_metadata).
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct FungibleTokenMetadataHolder {
+    metadata: LazyOption<FungibleTokenMetadata>,
+}
+
+impl FungibleTokenMetadataHolder {
+    pub fn new<S>(prefix: S, metadata: &FungibleTokenMetadata) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        metadata.assert_valid();
+        Self { metadata: LazyOption::new(prefix, Some(metadata)) }
+    }
+
+    /// Replaces the metadata if the predecessor is `owner_id`.
+    pub fn update(&mut self, owner_id: &AccountId, metadata: FungibleTokenMetadata) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        require!(&sender_id == owner_id, "Only the owner can update the metadata");
+        metadata.assert_valid();
+        self.metadata.set(&metadata);
+        MetadataUpdated { sender_id: &sender_id, contract_id: None }.emit();
+    }
+}
+
+impl FungibleTokenMetadataProvider for FungibleTokenMetadataHolder {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        self.metadata.get().unwrap_or_else(|| env::panic_str("The metadata is not set"))
+    }
+}
+
+impl FungibleTokenCommunityMetadataProvider for FungibleToken {
+    fn ft_community_metadata(&self, contract_id: AccountId) -> Option<FungibleTokenMetadata> {
+        self.community_metadata.get(&contract_id)
+    }
+
+    fn ft_set_community_metadata(&mut self, metadata: FungibleTokenMetadata) {
+        metadata.assert_valid();
+        let initial_storage_usage = env::storage_usage();
+        let contract_id = env::predecessor_account_id();
+        self.community_metadata.insert(&contract_id, &metadata);
+        self.internal_settle_storage(initial_storage_usage);
+        MetadataUpdated { sender_id: &contract_id, contract_id: Some(&contract_id) }.emit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{community, set_caller};
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::ONE_NEAR;
+
+    fn metadata(reference_hash: Option<Vec<u8>>) -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Community".to_string(),
+            symbol: "COM".to_string(),
+            icon: None,
+            reference: reference_hash.as_ref().map(|_| "https://example.com/community.json".to_string()),
+            reference_hash: reference_hash.map(Base64VecU8),
+            decimals: 18,
+        }
+    }
+
+    fn setup() -> FungibleToken {
+        set_caller(community(), ONE_NEAR);
+        FungibleToken::new(b"t".to_vec())
+    }
+
+    #[test]
+    fn set_community_metadata() {
+        let mut token = setup();
+        assert!(token.ft_community_metadata(community()).is_none());
+        token.ft_set_community_metadata(metadata(Some(vec![0; 32])));
+        assert_eq!(token.ft_community_metadata(community()).unwrap().symbol, "COM");
+    }
+
+    #[test]
+    fn update_metadata() {
+        set_caller(community(), 1);
+        let mut holder = FungibleTokenMetadataHolder::new(b"m".to_vec(), &metadata(None));
+        holder.update(&community(), FungibleTokenMetadata { symbol: "NEW".to_string(), ..metadata(None) });
+        assert_eq!(holder.ft_metadata().symbol, "NEW");
+        assert!(get_logs()[0].contains(r#""event":"metadata_updated""#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can update the metadata")]
+    fn update_metadata_not_owner() {
+        set_caller(accounts(1), 1);
+        let mut holder = FungibleTokenMetadataHolder::new(b"m".to_vec(), &metadata(None));
+        holder.update(&community(), metadata(None));
+    }
+
+    #[test]
+    #[should_panic(expected = "Hash has to be 32 bytes")]
+    fn set_community_metadata_invalid() {
+        let mut token = setup();
+        token.ft_set_community_metadata(metadata(Some(vec![0; 31])));
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedMap};
+use near_sdk::{env, AccountId, Balance, StorageUsage};
+
+use crate::fungible_token::core_impl::{Account, FungibleToken};
+
+/// Layout of [`Account`] before deposits could expire.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AccountV0 {
+    pub contract_ids: UnorderedMap<Option<AccountId>, (Balance, Balance)>,
+    pub deposit_map: UnorderedMap<AccountId, HashMap<Option<AccountId>, Balance>>,
+}
+
+impl AccountV0 {
+    /// Keeps the balances and deposits and adds the expiry map under `prefix`, the prefix the
+    /// account was created with. The deposits carried over never expire.
+    pub fn into_account(self, prefix: String) -> Account {
+        Account {
+            contract_ids: self.contract_ids,
+            deposit_map: self.deposit_map,
+            deposit_expiry_map: LookupMap::new((prefix + "deposit_expiry").as_bytes()),
+        }
+    }
+}
+
+/// Layout of [`FungibleToken`] released in 0.0.8, before the deposit registry.
+/// A contract reads its old state with this in place of the token, then builds the token with
+/// [`FungibleToken::from_v0`].
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct FungibleTokenV0 {
+    pub accounts: LookupMap<AccountId, AccountV0>,
+    pub total_supply: AccountV0,
+    pub account_storage_usage: StorageUsage,
+}
+
+impl FungibleToken {
+    /// Builds the token from `old`, keeping its accounts and supply. The new fields start empty,
+    /// and the accounts keep the old layout until [`internal_migrate_account`](Self::internal_migrate_account)
+    /// rewrites them, which the token does itself before it first writes one.
+    pub fn from_v0(old: FungibleTokenV0) -> Self {
+        // A `LookupMap` is stored as its key prefix only, so the accounts are read from the same prefix.
+        let accounts = LookupMap::try_from_slice(&old.accounts.try_to_vec().unwrap()).unwrap();
+        Self::from_parts(accounts, old.total_supply.into_account("total_supply".to_string()))
+    }
+
+    /// Reads `account_id` in either layout. An account still in the old layout is returned in the
+    /// current one.
+    pub fn internal_get_account(&self, account_id: &AccountId) -> Option<Account> {
+        let raw_account = self.internal_read_raw_account(account_id)?;
+        match Account::try_from_slice(&raw_account) {
+            Ok(account) => Some(account),
+            Err(_) => Some(AccountV0::try_from_slice(&raw_account).ok()?.into_account(account_id.to_string()))
+        }
+    }
+
+    /// Like [`internal_unwrap_account`](Self::internal_unwrap_account), migrating the account
+    /// first if it is still in the old layout, so that it can be written back.
+    pub fn internal_unwrap_migrated_account(&mut self, account_id: &AccountId) -> Account {
+        self.internal_migrate_account(account_id);
+        self.internal_unwrap_account(account_id)
+    }
+
+    fn internal_read_raw_account(&self, account_id: &AccountId) -> Option<Vec<u8>> {
+        let prefix = Vec::<u8>::try_from_slice(&self.accounts.try_to_vec().unwrap()).unwrap();
+        env::storage_read(&[prefix, account_id.try_to_vec().unwrap()].concat())
+    }
+
+    /// Rewrites `account_id` in the current layout and indexes its deposits in the deposit registry.
+    /// Returns false if the account is not registered or was already migrated.
+    pub fn internal_migrate_account(&mut self, account_id: &AccountId) -> bool {
+        let old = match self.internal_read_raw_account(account_id).map(|raw_account| AccountV0::try_from_slice(&raw_account)) {
+            Some(Ok(old)) => old,
+            _ => return false
+        };
+        let account = old.into_account(account_id.to_string());
+        // `insert` would read the old value back in the current layout.
+        self.accounts.insert_raw(&account_id.try_to_vec().unwrap(), &account.try_to_vec().unwrap());
+        self.internal_index_deposits(account_id);
+        true
+    }
+
+    /// Sets the deposit registry entries of `account_id` to the deposits it holds.
+    /// Indexing an account again changes nothing.
+    pub fn internal_index_deposits(&mut self, account_id: &AccountId) {
+        let account = match self.internal_get_account(account_id) {
+            Some(account) => account,
+            None => return
+        };
+        for (contract_id, deposits) in account.deposit_map.iter() {
+            for (deposit_contract_id, amount) in deposits {
+                if let Some(deposit_contract_id) = deposit_contract_id {
+                    self.deposit_registry.set(&deposit_contract_id, &contract_id, account_id, amount);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::account::FungibleTokenAccount;
+    use crate::fungible_token::core::FungibleTokenCore;
+    use crate::fungible_token::deposit_registry::FungibleTokenDepositRegistry;
+    use crate::test_utils::{community, set_caller};
+    use near_sdk::test_utils::accounts;
+
+    fn account_v0(prefix: String) -> AccountV0 {
+        let mut account = AccountV0 {
+            contract_ids: UnorderedMap::new(prefix.as_bytes()),
+            deposit_map: UnorderedMap::new((prefix + "deposit").as_bytes()),
+        };
+        account.contract_ids.insert(&None, &(70, 100));
+        account.contract_ids.insert(&Some(community()), &(70, 100));
+        let deposits = HashMap::from([(Some(accounts(4)), 30), (None, 30)]);
+        account.deposit_map.insert(&community(), &deposits);
+        account
+    }
+
+    fn token_v0() -> FungibleToken {
+        set_caller(accounts(0), 0);
+        let mut old = FungibleTokenV0 {
+            accounts: LookupMap::new(b"t".to_vec()),
+            total_supply: account_v0("total_supply".to_string()),
+            account_storage_usage: 0,
+        };
+        old.accounts.insert(&accounts(1), &account_v0(accounts(1).to_string()));
+        FungibleToken::from_v0(old)
+    }
+
+    #[test]
+    fn migrate_account() {
+        let mut token = token_v0();
+        assert!(token.ft_depositors(accounts(4), community(), None, None).is_empty());
+        assert!(token.internal_migrate_account(&accounts(1)));
+        assert!(!token.internal_migrate_account(&accounts(1)));
+        assert!(!token.internal_migrate_account(&accounts(2)));
+        let account = token.internal_unwrap_account(&accounts(1));
+        assert_eq!(account.get_available_balance(&Some(community())), 70);
+        assert_eq!(account.get_deposit_expires_at(&community(), &accounts(4)), None);
+        assert_eq!(token.ft_deposit_of(accounts(1), community(), accounts(4)).0, 30);
+        assert_eq!(token.ft_depositors(accounts(4), community(), None, None).len(), 1);
+        assert_eq!(token.total_supply.get_total_balance(&Some(community())), 100);
+
+        token.internal_index_deposits(&accounts(1));
+        assert_eq!(token.ft_deposit_communities(accounts(4), None, None)[0].amount.0, 30);
+
+        token.internal_contract_withdraw(&accounts(1), 30, &community(), &accounts(4));
+        assert_eq!(token.internal_unwrap_account(&accounts(1)).get_available_balance(&None), 100);
+        assert!(token.ft_depositors(accounts(4), community(), None, None).is_empty());
+    }
+
+    #[test]
+    fn views_before_migrating() {
+        let token = token_v0();
+        assert_eq!(token.ft_balance_of(accounts(1), Some(community())).0, 70);
+        assert_eq!(token.ft_deposit_of(accounts(1), community(), accounts(4)).0, 30);
+        assert_eq!(token.ft_deposit_expires_at(accounts(1), community(), accounts(4)), None);
+    }
+
+    #[test]
+    fn deposit_before_migrating() {
+        let mut token = token_v0();
+        assert_eq!(token.ft_balance_of(accounts(1), Some(community())).0, 70);
+        token.internal_deposit(&accounts(1), 10, &community());
+        assert!(!token.internal_migrate_account(&accounts(1)));
+        assert_eq!(token.ft_balance_of(accounts(1), Some(community())).0, 80);
+        assert_eq!(token.ft_depositors(accounts(4), community(), None, None).len(), 1);
+
+        token.internal_contract_deposit_with_expiry(&accounts(1), 20, &community(), &accounts(4), None);
+        assert_eq!(token.ft_deposit_of(accounts(1), community(), accounts(4)).0, 50);
+        assert_eq!(token.ft_balance_of(accounts(1), Some(community())).0, 60);
+        assert_eq!(token.ft_total_supply(Some(community())).0, 110);
+    }
+}
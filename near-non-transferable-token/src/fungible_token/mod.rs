@@ -7,6 +7,17 @@ pub mod resolver;
 pub mod metadata;
 pub mod receiver;
 pub mod events;
+pub mod deposit_registry;
+pub mod migration;
+pub mod compat;
+pub mod multi_token;
+pub mod recovery;
+pub mod merge;
+pub mod clawback;
+pub mod consent;
+pub mod renounce;
+pub mod pause;
 
 pub use core_impl::FungibleToken;
+#[allow(unused_imports)]
 pub use macros::*;
\ No newline at end of file
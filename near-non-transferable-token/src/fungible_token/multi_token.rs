@@ -0,0 +1,123 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{ext_contract, AccountId};
+
+use crate::fungible_token::account::FungibleTokenAccount;
+use crate::fungible_token::core_impl::FungibleToken;
+use crate::fungible_token::metadata::FungibleTokenMetadata;
+
+/// The token id of a community is its `contract_id`.
+pub type TokenId = String;
+
+/// The base token metadata of NEP-245, filled from the community metadata.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MtBaseTokenMetadata {
+    pub name: String,
+    pub id: String,
+    pub symbol: Option<String>,
+    pub icon: Option<String>,
+    pub decimals: Option<String>,
+    pub base_uri: Option<String>,
+    pub reference: Option<String>,
+    pub copies: Option<u64>,
+    pub reference_hash: Option<String>,
+}
+
+impl MtBaseTokenMetadata {
+    fn new(token_id: &str, metadata: FungibleTokenMetadata) -> Self {
+        Self {
+            name: metadata.name,
+            id: token_id.to_string(),
+            symbol: Some(metadata.symbol),
+            icon: metadata.icon,
+            decimals: Some(metadata.decimals.to_string()),
+            base_uri: None,
+            reference: metadata.reference,
+            copies: None,
+            reference_hash: metadata.reference_hash.map(|reference_hash| near_sdk::base64::encode(reference_hash.0)),
+        }
+    }
+}
+
+/// The views of NEP-245 multi tokens, with each community as a token.
+/// Balances are the available balances of `ft_balance_of`, which leave the deposits out, and the
+/// supply is `ft_available_supply`, which counts them.
+#[ext_contract(ext_mt_view)]
+pub trait MultiTokenView {
+    fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128;
+
+    fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128>;
+
+    /// Returns what the community minted less what was burned, deposited tokens included, or
+    /// `None` if the community has never minted.
+    fn mt_supply(&self, token_id: TokenId) -> Option<U128>;
+
+    fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>>;
+
+    /// Returns `None` for the communities that have not set their metadata.
+    fn mt_metadata_base_by_token_id(&self, token_ids: Vec<TokenId>) -> Vec<Option<MtBaseTokenMetadata>>;
+}
+
+impl FungibleToken {
+    fn internal_mt_balance_of(&self, account_id: &AccountId, token_id: &str) -> U128 {
+        match (self.internal_get_account(account_id), token_id.parse::<AccountId>()) {
+            (Some(account), Ok(contract_id)) => account.get_available_balance(&Some(contract_id)).into(),
+            _ => 0.into()
+        }
+    }
+
+    fn internal_mt_supply(&self, token_id: &str) -> Option<U128> {
+        let contract_id = token_id.parse::<AccountId>().ok()?;
+        self.total_supply.contract_ids.get(&Some(contract_id)).map(|(available, _)| available.into())
+    }
+}
+
+impl MultiTokenView for FungibleToken {
+    fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128 {
+        self.internal_mt_balance_of(&account_id, &token_id)
+    }
+
+    fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128> {
+        token_ids.iter().map(|token_id| self.internal_mt_balance_of(&account_id, token_id)).collect()
+    }
+
+    fn mt_supply(&self, token_id: TokenId) -> Option<U128> {
+        self.internal_mt_supply(&token_id)
+    }
+
+    fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>> {
+        token_ids.iter().map(|token_id| self.internal_mt_supply(token_id)).collect()
+    }
+
+    fn mt_metadata_base_by_token_id(&self, token_ids: Vec<TokenId>) -> Vec<Option<MtBaseTokenMetadata>> {
+        token_ids
+            .iter()
+            .map(|token_id| {
+                let contract_id = token_id.parse::<AccountId>().ok()?;
+                self.community_metadata.get(&contract_id).map(|metadata| MtBaseTokenMetadata::new(token_id, metadata))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{community, registered_token};
+    use near_sdk::test_utils::accounts;
+
+    #[test]
+    fn community_balances() {
+        let mut token = registered_token(accounts(0), 0);
+        token.internal_deposit(&accounts(1), 100, &community());
+        token.internal_withdraw(&accounts(1), 20, &community());
+        token.internal_contract_deposit(&accounts(1), 10, &community(), &accounts(4));
+        assert_eq!(
+            token.mt_batch_balance_of(accounts(1), vec![community().to_string(), accounts(3).to_string(), "Not an id".to_string()]),
+            vec![U128(70), U128(0), U128(0)]
+        );
+        assert_eq!(token.mt_batch_supply(vec![community().to_string(), accounts(3).to_string()]), vec![Some(U128(80)), None]);
+        assert_eq!(token.mt_metadata_base_by_token_id(vec![community().to_string()]), vec![None]);
+    }
+}
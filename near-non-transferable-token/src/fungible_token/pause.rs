@@ -0,0 +1,236 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId};
+
+use crate::access_control::{AccessControl, Role};
+use crate::fungible_token::core_impl::FungibleToken;
+use crate::fungible_token::events::{Frozen, Paused, Unpaused};
+
+/// The operations a pause or a freeze stops. Each one is stopped independently.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Pause {
+    pub mint: bool,
+    pub burn: bool,
+    pub deposit: bool,
+    pub withdraw: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PausableOperation {
+    Mint,
+    Burn,
+    Deposit,
+    Withdraw,
+}
+
+impl PausableOperation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mint => "Minting",
+            Self::Burn => "Burning",
+            Self::Deposit => "Depositing",
+            Self::Withdraw => "Withdrawing",
+        }
+    }
+}
+
+impl Pause {
+    pub fn is_paused(&self, operation: PausableOperation) -> bool {
+        match operation {
+            PausableOperation::Mint => self.mint,
+            PausableOperation::Burn => self.burn,
+            PausableOperation::Deposit => self.deposit,
+            PausableOperation::Withdraw => self.withdraw,
+        }
+    }
+
+    /// Stops what either of the two stops.
+    pub fn or(&self, other: &Self) -> Self {
+        Self {
+            mint: self.mint || other.mint,
+            burn: self.burn || other.burn,
+            deposit: self.deposit || other.deposit,
+            withdraw: self.withdraw || other.withdraw,
+        }
+    }
+}
+
+/// Emergency stops: a global pause and a freeze per account, set by the global pausers, and a
+/// pause per community, set by the pausers of the community and the community itself. Once an
+/// admin timelock is set, only the community setters and a global pause that stops more than the
+/// current one work directly. The checks are built into
+/// every `FungibleTokenCore` method and into [`FungibleToken::internal_mint`].
+#[ext_contract(ext_ft_pause)]
+pub trait FungibleTokenPause {
+    /// Sets what is stopped for every account and community. Only a global pauser can call it,
+    /// with 1 yoctoNEAR attached.
+    fn ft_set_pause(&mut self, pause: Pause);
+
+    /// Sets what is stopped for the tokens of `contract_id`. Only a pauser of the community can
+    /// call it, with 1 yoctoNEAR attached.
+    fn ft_set_community_pause(&mut self, contract_id: AccountId, pause: Pause);
+
+    /// Sets what is stopped for `account_id`. Only a global pauser can call it,
+    /// with 1 yoctoNEAR attached.
+    fn ft_set_account_freeze(&mut self, account_id: AccountId, pause: Pause);
+
+    /// Returns what is stopped for `account_id` in `contract_id`, combining the global pause
+    /// with the pause of the community and the freeze of the account when given.
+    fn ft_pause_status(&self, account_id: Option<AccountId>, contract_id: Option<AccountId>) -> Pause;
+}
+
+impl FungibleToken {
+    /// Panics if `operation` is stopped for `account_id` in `contract_id`.
+    pub fn internal_assert_not_paused(&self, operation: PausableOperation, account_id: &AccountId, contract_id: &AccountId) {
+        require!(!self.pause.is_paused(operation), format!("{} is paused", operation.as_str()));
+        let community_paused = match self.community_pauses.get(contract_id) {
+            Some(pause) => pause.is_paused(operation),
+            None => false
+        };
+        require!(!community_paused, format!("{} is paused for this community", operation.as_str()));
+        let account_frozen = match self.account_freezes.get(account_id) {
+            Some(pause) => pause.is_paused(operation),
+            None => false
+        };
+        require!(!account_frozen, format!("{} is frozen for this account", operation.as_str()));
+    }
+
+    /// Sets the global pause if the predecessor holds the pauser role in `acl` globally.
+    pub fn set_pause(&mut self, acl: &AccessControl, pause: Pause) {
+        assert_one_yocto();
+        crate::require_role!(acl, Role::Pauser);
+        // Stopping more is an emergency stop, but lifting a stop has to wait for the timelock.
+        if pause.or(&self.pause) != pause {
+            acl.assert_not_timelocked(Role::Pauser, &env::predecessor_account_id(), None);
+        }
+        self.internal_set_pause(&env::predecessor_account_id(), pause);
+    }
+
+    /// Sets the pause of `contract_id` if the predecessor holds the pauser role in `acl` for it.
+    pub fn set_community_pause(&mut self, acl: &AccessControl, contract_id: &AccountId, pause: Pause) {
+        assert_one_yocto();
+        crate::require_role!(acl, Role::Pauser, contract_id);
+        acl.assert_not_timelocked(Role::Pauser, &env::predecessor_account_id(), Some(contract_id));
+        self.internal_set_community_pause(&env::predecessor_account_id(), contract_id, pause);
+    }
+
+    /// Sets the freeze of `account_id` if the predecessor holds the pauser role in `acl` globally.
+    pub fn set_account_freeze(&mut self, acl: &AccessControl, account_id: &AccountId, pause: Pause) {
+        assert_one_yocto();
+        crate::require_role!(acl, Role::Pauser);
+        acl.assert_not_timelocked(Role::Pauser, &env::predecessor_account_id(), None);
+        self.internal_set_account_freeze(&env::predecessor_account_id(), account_id, pause);
+    }
+
+    /// Sets the global pause on behalf of `sender_id`, without checking it.
+    pub fn internal_set_pause(&mut self, sender_id: &AccountId, pause: Pause) {
+        self.pause = pause;
+        if pause == Pause::default() {
+            Unpaused { sender_id, contract_id: None, account_id: None }.emit();
+        } else {
+            Paused { sender_id, contract_id: None, pause: &pause }.emit();
+        }
+    }
+
+    /// Sets the pause of `contract_id` on behalf of `sender_id`, without checking it.
+    pub fn internal_set_community_pause(&mut self, sender_id: &AccountId, contract_id: &AccountId, pause: Pause) {
+        if pause == Pause::default() {
+            self.community_pauses.remove(contract_id);
+            Unpaused { sender_id, contract_id: Some(contract_id), account_id: None }.emit();
+        } else {
+            self.community_pauses.insert(contract_id, &pause);
+            Paused { sender_id, contract_id: Some(contract_id), pause: &pause }.emit();
+        }
+    }
+
+    /// Sets the freeze of `account_id` on behalf of `sender_id`, without checking it.
+    pub fn internal_set_account_freeze(&mut self, sender_id: &AccountId, account_id: &AccountId, pause: Pause) {
+        if pause == Pause::default() {
+            self.account_freezes.remove(account_id);
+            Unpaused { sender_id, contract_id: None, account_id: Some(account_id) }.emit();
+        } else {
+            self.account_freezes.insert(account_id, &pause);
+            Frozen { sender_id, account_id, pause: &pause }.emit();
+        }
+    }
+
+    pub fn pause_status(&self, account_id: Option<AccountId>, contract_id: Option<AccountId>) -> Pause {
+        let mut pause = self.pause;
+        if let Some(community_pause) = contract_id.and_then(|contract_id| self.community_pauses.get(&contract_id)) {
+            pause = pause.or(&community_pause);
+        }
+        if let Some(account_freeze) = account_id.and_then(|account_id| self.account_freezes.get(&account_id)) {
+            pause = pause.or(&account_freeze);
+        }
+        pause
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::core::FungibleTokenCore;
+    use crate::test_utils::{community, registered_token, set_caller};
+    use near_sdk::test_utils::{accounts, get_logs};
+
+    fn setup() -> (AccessControl, FungibleToken) {
+        let mut token = registered_token(accounts(0), 1);
+        token.internal_mint(&accounts(1), 100, &community(), None);
+        let mut acl = AccessControl::new(b"r".to_vec(), &accounts(0));
+        acl.internal_grant_role(Role::Pauser, &accounts(0), None, &accounts(0));
+        (acl, token)
+    }
+
+    #[test]
+    fn pause_status() {
+        let (acl, mut token) = setup();
+        token.set_pause(&acl, Pause { mint: true, ..Default::default() });
+        assert!(get_logs().last().unwrap().contains(r#""event":"paused","data":[{"sender_id":"alice","pause":{"mint":true,"burn":false,"deposit":false,"withdraw":false}}]"#));
+        set_caller(community(), 1);
+        token.set_community_pause(&acl, &community(), Pause { burn: true, ..Default::default() });
+        let status = token.pause_status(Some(accounts(1)), Some(community()));
+        assert_eq!(status, Pause { mint: true, burn: true, deposit: false, withdraw: false });
+        assert_eq!(token.pause_status(None, Some(accounts(3))), Pause { mint: true, ..Default::default() });
+        token.set_community_pause(&acl, &community(), Pause::default());
+        assert!(get_logs().last().unwrap().contains(r#""event":"unpaused","data":[{"sender_id":"community","contract_id":"community"}]"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "The change has to go through the admin timelock")]
+    fn timelocked_unpause() {
+        let (mut acl, mut token) = setup();
+        acl.timelocked = true;
+        token.set_pause(&acl, Pause { mint: true, ..Default::default() });
+        token.set_pause(&acl, Pause { mint: true, burn: true, ..Default::default() });
+        assert_eq!(token.pause_status(None, None), Pause { mint: true, burn: true, ..Default::default() });
+        token.set_pause(&acl, Pause { mint: true, ..Default::default() });
+    }
+
+    #[test]
+    #[should_panic(expected = "Burning is frozen for this account")]
+    fn frozen_burn() {
+        let (acl, mut token) = setup();
+        token.set_account_freeze(&acl, &accounts(1), Pause { burn: true, ..Default::default() });
+        assert!(get_logs().last().unwrap().contains(r#""event":"frozen","data":[{"sender_id":"alice","account_id":"bob","pause""#));
+        set_caller(accounts(1), 1);
+        token.ft_burn(community(), 10.into(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minting is paused for this community")]
+    fn community_paused_mint() {
+        let (acl, mut token) = setup();
+        set_caller(community(), 1);
+        token.set_community_pause(&acl, &community(), Pause { mint: true, ..Default::default() });
+        token.internal_mint(&accounts(1), 10, &community(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account is missing the pauser role")]
+    fn pause_by_stranger() {
+        let (acl, mut token) = setup();
+        set_caller(accounts(2), 1);
+        token.set_community_pause(&acl, &community(), Pause { mint: true, ..Default::default() });
+    }
+}
@@ -45,3 +45,16 @@ pub trait FungibleTokenReceiver {
         msg: String
     ) -> PromiseOrValue<U128>;
 }
+
+#[ext_contract(ext_ft_slash_receiver)]
+pub trait FungibleTokenSlashReceiver {
+    /// Called by the token contract after community `contract_id` slashed `amount` of the deposit
+    /// `owner_id` held with this contract. The tokens are already burned, so this is a notice only.
+    fn ft_on_slash(
+        &mut self,
+        owner_id: AccountId,
+        contract_id: AccountId,
+        amount: U128,
+        reason: Option<String>
+    );
+}
@@ -0,0 +1,387 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId};
+
+use crate::access_control::{AccessControl, Role};
+use crate::fungible_token::account::FungibleTokenAccount;
+use crate::fungible_token::core_impl::FungibleToken;
+use crate::fungible_token::events::{AccountRecovered, RecoveryApproved, RecoveryCancelled, RecoveryGuardiansUpdated, RecoveryRequested};
+
+/// Seven days, in nanoseconds.
+pub const DEFAULT_RECOVERY_DELAY: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+/// The guardians an account chose in advance, and how many of them have to approve a recovery.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RecoveryGuardians {
+    pub guardians: Vec<AccountId>,
+    pub threshold: u32,
+}
+
+/// A pending recovery of an account onto `new_account_id`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Recovery {
+    pub new_account_id: AccountId,
+    pub requested_at: U64,
+    pub approvals: Vec<AccountId>,
+}
+
+/// Recovery of an account whose keys are lost. Since the token cannot be transferred, a
+/// recovery moves the whole account: every community balance, every deposit with its expiry,
+/// and the storage it paid for. The supply does not change.
+///
+/// A recovery is requested and approved either by the guardians of the account, up to their
+/// threshold, or by every community the account holds tokens of, available or deposited, through
+/// the community itself or one of its community admins. It can be finalized by anyone once
+/// approved and once the recovery delay has passed, and the account or one of its guardians can
+/// cancel it until then.
+#[ext_contract(ext_ft_recovery)]
+pub trait FungibleTokenRecovery {
+    /// Replaces the guardians of the caller's account. An empty list removes them.
+    /// Requires at least 1 yoctoNEAR attached, and a deposit to cover the added storage.
+    fn ft_set_recovery_guardians(&mut self, guardians: Vec<AccountId>, threshold: u32);
+
+    fn ft_recovery_guardians(&self, account_id: AccountId) -> Option<RecoveryGuardians>;
+
+    /// Requests the recovery of `account_id` onto `new_account_id`, which must not hold any tokens,
+    /// and approves it. Only a guardian or a community of the account can call it.
+    /// Requires a deposit to cover the added storage.
+    fn ft_request_recovery(&mut self, account_id: AccountId, new_account_id: AccountId);
+
+    /// Approves the pending recovery of `account_id`. Only a guardian or a community of the
+    /// account can call it. Requires 1 yoctoNEAR attached.
+    fn ft_approve_recovery(&mut self, account_id: AccountId);
+
+    /// Cancels the pending recovery of `account_id`. Only the account or one of its guardians
+    /// can call it. Requires 1 yoctoNEAR attached.
+    fn ft_cancel_recovery(&mut self, account_id: AccountId);
+
+    /// Moves `account_id` onto the new account of its approved recovery, once the recovery
+    /// delay has passed. Requires a deposit to cover the added storage.
+    fn ft_finalize_recovery(&mut self, account_id: AccountId);
+
+    fn ft_recovery_of(&self, account_id: AccountId) -> Option<Recovery>;
+}
+
+impl FungibleToken {
+    /// The communities `account_id` holds tokens of, available or deposited.
+    fn internal_communities_of(&self, account_id: &AccountId) -> Vec<AccountId> {
+        let account = self.internal_unwrap_account(account_id);
+        let mut communities: Vec<AccountId> = account
+            .contract_ids
+            .iter()
+            .filter_map(|(contract_id, (available, _))| contract_id.filter(|_| available > 0))
+            .collect();
+        for contract_id in account.deposit_map.keys() {
+            if !communities.contains(&contract_id) {
+                communities.push(contract_id);
+            }
+        }
+        communities
+    }
+
+    fn internal_is_guardian(&self, account_id: &AccountId, guardian_id: &AccountId) -> bool {
+        match self.recovery_guardians.get(account_id) {
+            Some(guardians) => guardians.guardians.contains(guardian_id),
+            None => false
+        }
+    }
+
+    /// The approvals `sender_id` gives to the recovery of `account_id`: its own as a guardian, and
+    /// those of the communities of the account it acts for.
+    fn internal_recovery_approvals(&self, account_id: &AccountId, sender_id: &AccountId, acts_for: impl Fn(&AccountId) -> bool) -> Vec<AccountId> {
+        let mut approvals: Vec<AccountId> = self.internal_communities_of(account_id).into_iter().filter(|contract_id| acts_for(contract_id)).collect();
+        if self.internal_is_guardian(account_id, sender_id) {
+            approvals.push(sender_id.clone());
+        }
+        approvals
+    }
+
+    /// Whether the guardians reached their threshold, or every community of the account approved.
+    pub fn internal_is_recovery_approved(&self, account_id: &AccountId, recovery: &Recovery) -> bool {
+        if recovery.approvals.is_empty() {
+            return false;
+        }
+        if let Some(guardians) = self.recovery_guardians.get(account_id) {
+            let approvals = guardians.guardians.iter().filter(|guardian| recovery.approvals.contains(guardian)).count();
+            if approvals >= guardians.threshold as usize {
+                return true;
+            }
+        }
+        let communities = self.internal_communities_of(account_id);
+        !communities.is_empty() && communities.iter().all(|contract_id| recovery.approvals.contains(contract_id))
+    }
+
+    /// Whether `account_id` can take over a recovered account: it is not registered, or it is
+    /// registered but holds no tokens of any community.
+    fn internal_can_receive_recovery(&self, account_id: &AccountId) -> bool {
+        match self.internal_get_account(account_id) {
+            Some(account) => account.get_total_balance(&None) == 0 && account.deposit_map.is_empty(),
+            None => true
+        }
+    }
+
+    fn internal_unwrap_recovery(&self, account_id: &AccountId) -> Recovery {
+        self.recoveries
+            .get(account_id)
+            .unwrap_or_else(|| env::panic_str(format!("No recovery of {} is pending", account_id).as_str()))
+    }
+
+    fn internal_request_recovery(&mut self, account_id: AccountId, new_account_id: AccountId, acts_for: impl Fn(&AccountId) -> bool) {
+        let initial_storage_usage = env::storage_usage();
+        let sender_id = env::predecessor_account_id();
+        require!(self.accounts.contains_key(&account_id), "The account is not registered");
+        require!(account_id != new_account_id, "The accounts should be different");
+        require!(self.internal_can_receive_recovery(&new_account_id), "The new account already holds tokens");
+        require!(self.recoveries.get(&account_id).is_none(), "A recovery is already pending");
+        let approvals = self.internal_recovery_approvals(&account_id, &sender_id, acts_for);
+        require!(!approvals.is_empty(), "Only a guardian or a community of the account can request its recovery");
+        let recovery = Recovery {
+            new_account_id: new_account_id.clone(),
+            requested_at: env::block_timestamp().into(),
+            approvals,
+        };
+        self.recoveries.insert(&account_id, &recovery);
+        RecoveryRequested { account_id: &account_id, new_account_id: &new_account_id, sender_id: &sender_id }.emit();
+        self.internal_settle_storage(initial_storage_usage);
+    }
+
+    fn internal_approve_recovery(&mut self, account_id: AccountId, acts_for: impl Fn(&AccountId) -> bool) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let mut recovery = self.internal_unwrap_recovery(&account_id);
+        let approvals = self.internal_recovery_approvals(&account_id, &sender_id, acts_for);
+        require!(!approvals.is_empty(), "Only a guardian or a community of the account can approve its recovery");
+        let approvals: Vec<AccountId> = approvals.into_iter().filter(|approval| !recovery.approvals.contains(approval)).collect();
+        require!(!approvals.is_empty(), "The recovery is already approved by the caller");
+        recovery.approvals.extend(approvals);
+        self.recoveries.insert(&account_id, &recovery);
+        RecoveryApproved { account_id: &account_id, sender_id: &sender_id }.emit();
+    }
+
+    /// Requests the recovery of `account_id` like `ft_request_recovery`, where a community admin
+    /// of a community in `acl` acts for it.
+    pub fn request_recovery(&mut self, acl: &AccessControl, account_id: AccountId, new_account_id: AccountId) {
+        let sender_id = env::predecessor_account_id();
+        self.internal_request_recovery(account_id, new_account_id, |contract_id| acl.has_community_role(Role::CommunityAdmin, &sender_id, contract_id))
+    }
+
+    /// Approves the recovery of `account_id` like `ft_approve_recovery`, where a community admin
+    /// of a community in `acl` acts for it.
+    pub fn approve_recovery(&mut self, acl: &AccessControl, account_id: AccountId) {
+        let sender_id = env::predecessor_account_id();
+        self.internal_approve_recovery(account_id, |contract_id| acl.has_community_role(Role::CommunityAdmin, &sender_id, contract_id))
+    }
+}
+
+impl FungibleTokenRecovery for FungibleToken {
+    fn ft_set_recovery_guardians(&mut self, mut guardians: Vec<AccountId>, threshold: u32) {
+        require!(env::attached_deposit() >= 1, "Requires attached deposit of at least 1 yoctoNEAR");
+        let initial_storage_usage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        require!(self.accounts.contains_key(&account_id), "The account is not registered");
+        guardians.sort();
+        guardians.dedup();
+        require!(!guardians.contains(&account_id), "The account cannot be its own guardian");
+        let threshold = if guardians.is_empty() {
+            self.recovery_guardians.remove(&account_id);
+            0
+        } else {
+            require!(threshold > 0 && threshold as usize <= guardians.len(), "The threshold should be between 1 and the number of guardians");
+            self.recovery_guardians.insert(&account_id, &RecoveryGuardians { guardians: guardians.clone(), threshold });
+            threshold
+        };
+        RecoveryGuardiansUpdated { account_id: &account_id, guardians: &guardians, threshold }.emit();
+        self.internal_settle_storage(initial_storage_usage);
+    }
+
+    fn ft_recovery_guardians(&self, account_id: AccountId) -> Option<RecoveryGuardians> {
+        self.recovery_guardians.get(&account_id)
+    }
+
+    fn ft_request_recovery(&mut self, account_id: AccountId, new_account_id: AccountId) {
+        let sender_id = env::predecessor_account_id();
+        self.internal_request_recovery(account_id, new_account_id, |contract_id| contract_id == &sender_id)
+    }
+
+    fn ft_approve_recovery(&mut self, account_id: AccountId) {
+        let sender_id = env::predecessor_account_id();
+        self.internal_approve_recovery(account_id, |contract_id| contract_id == &sender_id)
+    }
+
+    fn ft_cancel_recovery(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.internal_unwrap_recovery(&account_id);
+        require!(
+            sender_id == account_id || self.internal_is_guardian(&account_id, &sender_id),
+            "Only the account or one of its guardians can cancel its recovery"
+        );
+        self.recoveries.remove(&account_id);
+        RecoveryCancelled { account_id: &account_id, sender_id: &sender_id }.emit();
+    }
+
+    fn ft_finalize_recovery(&mut self, account_id: AccountId) {
+        let initial_storage_usage = env::storage_usage();
+        let recovery = self.internal_unwrap_recovery(&account_id);
+        require!(
+            env::block_timestamp() >= recovery.requested_at.0.saturating_add(self.recovery_delay),
+            "The recovery delay has not passed yet"
+        );
+        require!(self.internal_is_recovery_approved(&account_id, &recovery), "The recovery is not approved");
+        require!(self.internal_can_receive_recovery(&recovery.new_account_id), "The new account already holds tokens");
+        self.recoveries.remove(&account_id);
+        self.recovery_guardians.remove(&account_id);
+        self.merge_proposals.remove(&account_id);
+        self.internal_move_account(&account_id, &recovery.new_account_id);
+        AccountRecovered { account_id: &account_id, new_account_id: &recovery.new_account_id }.emit();
+        self.internal_settle_storage(initial_storage_usage);
+    }
+
+    fn ft_recovery_of(&self, account_id: AccountId) -> Option<Recovery> {
+        self.recoveries.get(&account_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::core::FungibleTokenCore;
+    use crate::fungible_token::merge::FungibleTokenMerge;
+    use crate::test_utils::{community, registered_token, set_caller_at as set_caller};
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::ONE_NEAR;
+
+    fn setup() -> FungibleToken {
+        let mut token = registered_token(accounts(1), ONE_NEAR);
+        token.internal_deposit(&accounts(1), 1000, &community());
+        token.internal_contract_deposit_with_expiry(&accounts(1), 300, &community(), &accounts(4), Some(10));
+        token
+    }
+
+    #[test]
+    fn recover_with_guardians() {
+        let mut token = setup();
+        token.ft_set_recovery_guardians(vec![accounts(2), accounts(3)], 2);
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"recovery_guardians_updated","data":[{"account_id":"bob","guardians":["charlie","danny"],"threshold":2}]"#)));
+
+        set_caller(accounts(2), ONE_NEAR, 1);
+        token.ft_request_recovery(accounts(1), accounts(5));
+        assert!(!token.internal_is_recovery_approved(&accounts(1), &token.ft_recovery_of(accounts(1)).unwrap()));
+        set_caller(accounts(3), 1, 1);
+        token.ft_approve_recovery(accounts(1));
+
+        set_caller(accounts(0), ONE_NEAR, DEFAULT_RECOVERY_DELAY + 1);
+        token.ft_finalize_recovery(accounts(1));
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"account_recovered","data":[{"account_id":"bob","new_account_id":"fargo"}]"#)));
+        assert!(!token.accounts.contains_key(&accounts(1)));
+        assert_eq!(token.ft_balance_of(accounts(5), Some(community())).0, 700);
+        assert_eq!(token.ft_total_balance_of(accounts(5), Some(community())).0, 1000);
+        let account = token.internal_unwrap_account(&accounts(5));
+        assert_eq!(account.get_deposit_balance(&Some(community()), &Some(accounts(4))), 300);
+        assert_eq!(account.get_deposit_expires_at(&community(), &accounts(4)), Some(10));
+        assert_eq!(token.deposit_registry.depositors.get(&(accounts(4), community())).unwrap().get(&accounts(5)), Some(300));
+        assert_eq!(token.ft_total_supply(Some(community())).0, 1000);
+    }
+
+    #[test]
+    fn recover_with_communities() {
+        let mut token = setup();
+        set_caller(community(), ONE_NEAR, 0);
+        token.ft_request_recovery(accounts(1), accounts(5));
+        assert!(token.internal_is_recovery_approved(&accounts(1), &token.ft_recovery_of(accounts(1)).unwrap()));
+        set_caller(accounts(0), 0, DEFAULT_RECOVERY_DELAY);
+        token.ft_finalize_recovery(accounts(1));
+        assert_eq!(token.ft_total_balance_of(accounts(5), None).0, 1000);
+    }
+
+    #[test]
+    fn recover_with_community_admins() {
+        let mut token = setup();
+        token.internal_deposit(&accounts(1), 10, &accounts(3));
+        let mut acl = AccessControl::new(b"r".to_vec(), &accounts(0));
+        acl.internal_grant_role(Role::CommunityAdmin, &accounts(2), Some(&community()), &community());
+        acl.internal_grant_role(Role::CommunityAdmin, &accounts(2), Some(&accounts(3)), &accounts(3));
+        set_caller(accounts(2), ONE_NEAR, 0);
+        token.request_recovery(&acl, accounts(1), accounts(5));
+        assert!(token.internal_is_recovery_approved(&accounts(1), &token.ft_recovery_of(accounts(1)).unwrap()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only a guardian or a community of the account can approve its recovery")]
+    fn approve_by_admin_of_other_community() {
+        let mut token = setup();
+        let mut acl = AccessControl::new(b"r".to_vec(), &accounts(0));
+        acl.internal_grant_role(Role::CommunityAdmin, &accounts(2), Some(&accounts(3)), &accounts(3));
+        set_caller(community(), ONE_NEAR, 0);
+        token.request_recovery(&acl, accounts(1), accounts(5));
+        set_caller(accounts(2), 1, 0);
+        token.approve_recovery(&acl, accounts(1));
+    }
+
+    #[test]
+    fn recover_onto_empty_registered_account() {
+        let mut token = setup();
+        token.internal_register_account(&accounts(5));
+        token.internal_register_account(&accounts(2));
+        token.ft_propose_merge(accounts(2));
+        set_caller(community(), ONE_NEAR, 0);
+        token.ft_request_recovery(accounts(1), accounts(5));
+        set_caller(accounts(0), 0, DEFAULT_RECOVERY_DELAY);
+        token.ft_finalize_recovery(accounts(1));
+        assert_eq!(token.ft_total_balance_of(accounts(5), None).0, 1000);
+        assert_eq!(token.ft_merge_proposal_of(accounts(1)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of at least 1 yoctoNEAR")]
+    fn set_guardians_without_deposit() {
+        let mut token = setup();
+        set_caller(accounts(1), 0, 0);
+        token.ft_set_recovery_guardians(vec![accounts(2)], 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The new account already holds tokens")]
+    fn recover_onto_account_with_tokens() {
+        let mut token = setup();
+        token.internal_register_account(&accounts(5));
+        set_caller(community(), ONE_NEAR, 0);
+        token.ft_request_recovery(accounts(1), accounts(5));
+        token.internal_deposit(&accounts(5), 10, &accounts(3));
+        set_caller(accounts(0), 0, DEFAULT_RECOVERY_DELAY);
+        token.ft_finalize_recovery(accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "The recovery delay has not passed yet")]
+    fn recover_before_delay() {
+        let mut token = setup();
+        set_caller(community(), ONE_NEAR, 0);
+        token.ft_request_recovery(accounts(1), accounts(5));
+        set_caller(accounts(0), 0, DEFAULT_RECOVERY_DELAY - 1);
+        token.ft_finalize_recovery(accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the account or one of its guardians can cancel its recovery")]
+    fn cancel_by_community_minted_to_the_account() {
+        let mut token = setup();
+        set_caller(community(), ONE_NEAR, 0);
+        token.ft_request_recovery(accounts(1), accounts(5));
+        token.internal_mint(&accounts(1), 1, &accounts(3), None);
+        assert!(!token.internal_is_recovery_approved(&accounts(1), &token.ft_recovery_of(accounts(1)).unwrap()));
+        set_caller(accounts(3), 1, 0);
+        token.ft_cancel_recovery(accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only a guardian or a community of the account can request its recovery")]
+    fn request_by_stranger() {
+        let mut token = setup();
+        set_caller(accounts(2), ONE_NEAR, 0);
+        token.ft_request_recovery(accounts(1), accounts(2));
+    }
+}
@@ -0,0 +1,184 @@
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId};
+
+use crate::fungible_token::account::FungibleTokenAccount;
+use crate::fungible_token::core_impl::FungibleToken;
+use crate::fungible_token::events::{CommunityRenounced, FtBurn, FtMintRejected};
+use crate::fungible_token::pause::PausableOperation;
+
+/// Lets a holder disown the tokens of a community, and refuse its further mints.
+#[ext_contract(ext_ft_renounce)]
+pub trait FungibleTokenRenounce {
+    /// Burns the caller's whole available balance of `contract_id` like `ft_burn`, removes the
+    /// community from the caller's account and takes what the caller received off the supply.
+    /// The deposits of the community have to be withdrawn first. With `block`, the pending mints
+    /// of the community are rejected and further mints through `internal_mint` fail. The storage
+    /// the renounce frees is refunded to the caller, up to what the caller paid for storage.
+    /// Returns the burned amount.
+    /// Requires 1 yoctoNEAR attached.
+    fn ft_renounce(&mut self, contract_id: AccountId, block: Option<bool>) -> U128;
+
+    /// Accepts mints of `contract_id` again. Requires 1 yoctoNEAR attached.
+    fn ft_unblock_community(&mut self, contract_id: AccountId);
+
+    fn ft_blocked_communities(&self, account_id: AccountId) -> Vec<AccountId>;
+}
+
+impl FungibleTokenRenounce for FungibleToken {
+    fn ft_renounce(&mut self, contract_id: AccountId, block: Option<bool>) -> U128 {
+        assert_one_yocto();
+        let initial_storage_usage = env::storage_usage();
+        let owner_id = env::predecessor_account_id();
+        self.internal_assert_not_paused(PausableOperation::Burn, &owner_id, &contract_id);
+        let account = self.internal_unwrap_account(&owner_id);
+        require!(account.is_registered(&contract_id), "The account holds no tokens of this community");
+        require!(account.deposit_map.get(&contract_id).is_none(), "The deposits of this community should be withdrawn first");
+
+        let available = account.get_available_balance(&Some(contract_id.clone()));
+        if available > 0 {
+            self.internal_withdraw(&owner_id, available, &contract_id);
+            FtBurn { owner_id: &owner_id, contract_id: &contract_id, receiver_id: None, amount: &available.into(), memo: None }.emit();
+        }
+        let mut account = self.internal_unwrap_migrated_account(&owner_id);
+        let (_, total) = account.contract_ids.remove(&Some(contract_id.clone())).unwrap_or((0, 0));
+        let (aggregate_available, aggregate_total) = account.contract_ids.get(&None).unwrap_or((0, 0));
+        account.contract_ids.insert(&None, &(aggregate_available, aggregate_total.saturating_sub(total)));
+        self.accounts.insert(&owner_id, &account);
+        for key in [Some(contract_id.clone()), None] {
+            let (supply_available, supply_total) = self.total_supply.contract_ids.get(&key).unwrap_or((0, 0));
+            self.total_supply.contract_ids.insert(&key, &(supply_available, supply_total.saturating_sub(total)));
+        }
+
+        let blocked = block.unwrap_or(false);
+        if blocked {
+            let mut blocked_communities = self.blocked_communities.get(&owner_id).unwrap_or_default();
+            if !blocked_communities.contains(&contract_id) {
+                blocked_communities.push(contract_id.clone());
+                self.blocked_communities.insert(&owner_id, &blocked_communities);
+            }
+            for pending_mint in self.internal_remove_pending_mints(&owner_id, &contract_id) {
+                FtMintRejected { owner_id: &owner_id, contract_id: &contract_id, amount: &pending_mint.amount, memo: pending_mint.memo.as_deref() }.emit();
+            }
+        }
+        CommunityRenounced { owner_id: &owner_id, contract_id: &contract_id, blocked }.emit();
+        self.internal_refund_storage(&owner_id, initial_storage_usage.saturating_sub(env::storage_usage()));
+        available.into()
+    }
+
+    fn ft_unblock_community(&mut self, contract_id: AccountId) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        let mut blocked_communities = self.blocked_communities.get(&owner_id).unwrap_or_default();
+        require!(blocked_communities.contains(&contract_id), "The community is not blocked");
+        blocked_communities.retain(|blocked| blocked != &contract_id);
+        if blocked_communities.is_empty() {
+            self.blocked_communities.remove(&owner_id);
+        } else {
+            self.blocked_communities.insert(&owner_id, &blocked_communities);
+        }
+    }
+
+    fn ft_blocked_communities(&self, account_id: AccountId) -> Vec<AccountId> {
+        self.blocked_communities.get(&account_id).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fungible_token::consent::{ConsentPolicy, FungibleTokenConsent};
+    use crate::fungible_token::core::FungibleTokenCore;
+    use crate::test_utils::{community, registered_token, set_caller};
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs};
+    use near_sdk::{Balance, ONE_NEAR};
+
+    fn setup() -> FungibleToken {
+        let mut token = registered_token(accounts(1), ONE_NEAR);
+        token.internal_deposit(&accounts(1), 100, &community());
+        token.internal_deposit(&accounts(1), 50, &accounts(3));
+        token
+    }
+
+    #[test]
+    fn renounce_and_block() {
+        let mut token = setup();
+        set_caller(accounts(1), 1);
+        assert_eq!(token.ft_renounce(community(), Some(true)).0, 100);
+        assert!(get_logs().last().unwrap().contains(r#""event":"community_renounced","data":[{"owner_id":"bob","contract_id":"community","blocked":true}]"#));
+        let account = token.internal_unwrap_account(&accounts(1));
+        assert!(!account.is_registered(&community()));
+        assert_eq!(token.ft_total_balance_of(accounts(1), None).0, 50);
+        assert_eq!(token.ft_balance_of(accounts(1), None).0, 50);
+        assert_eq!(token.ft_available_supply(Some(community())).0, 0);
+        assert_eq!(token.ft_total_supply(Some(community())).0, 0);
+        assert_eq!(token.ft_total_supply(None).0, 50);
+        assert_eq!(token.ft_blocked_communities(accounts(1)), vec![community()]);
+    }
+
+    #[test]
+    fn renounce_rejects_pending_mints() {
+        let mut token = setup();
+        token.ft_set_consent_policy(ConsentPolicy::Pending);
+        token.internal_mint(&accounts(1), 10, &community(), None);
+        token.internal_mint(&accounts(1), 20, &accounts(3), None);
+        set_caller(accounts(1), 1);
+        token.ft_renounce(community(), Some(true));
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"ft_mint_rejected","data":[{"owner_id":"bob","contract_id":"community","amount":"10"}]"#)));
+        assert_eq!(token.ft_pending_mints(accounts(1)).len(), 1);
+        assert_eq!(token.ft_pending_mints(accounts(1))[0].contract_id, accounts(3));
+    }
+
+    /// Renounces `community()` for `accounts(1)`, and returns the storage the renounce freed
+    /// and the transfers it made.
+    fn renounce_transfers(token: &mut FungibleToken) -> (Balance, Vec<VmAction>) {
+        set_caller(accounts(1), 1);
+        let initial_storage_usage = env::storage_usage();
+        token.ft_renounce(community(), None);
+        let freed = Balance::from(initial_storage_usage - env::storage_usage()) * env::storage_byte_cost();
+        (freed, get_created_receipts().into_iter().flat_map(|receipt| receipt.actions).collect())
+    }
+
+    #[test]
+    fn renounce_refunds_nothing_unpaid() {
+        let mut token = setup();
+        let (freed, transfers) = renounce_transfers(&mut token);
+        assert!(freed > 0);
+        assert!(transfers.is_empty());
+        assert_eq!(token.storage_paid.get(&accounts(1)), None);
+    }
+
+    #[test]
+    fn renounce_refunds_up_to_the_paid_storage() {
+        let mut token = setup();
+        token.storage_paid.insert(&accounts(1), &ONE_NEAR);
+        let (freed, transfers) = renounce_transfers(&mut token);
+        assert_eq!(transfers, vec![VmAction::Transfer { deposit: freed }]);
+        assert_eq!(token.storage_paid.get(&accounts(1)), Some(ONE_NEAR - freed));
+    }
+
+    #[test]
+    fn renounce_refunds_at_most_the_paid_storage() {
+        let mut token = setup();
+        token.storage_paid.insert(&accounts(1), &1);
+        let (_, transfers) = renounce_transfers(&mut token);
+        assert_eq!(transfers, vec![VmAction::Transfer { deposit: 1 }]);
+        assert_eq!(token.storage_paid.get(&accounts(1)), Some(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn renounce_requires_one_yocto() {
+        let mut token = setup();
+        token.ft_renounce(community(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account blocked the tokens of this community")]
+    fn mint_after_block() {
+        let mut token = setup();
+        set_caller(accounts(1), 1);
+        token.ft_renounce(community(), Some(true));
+        token.internal_mint(&accounts(1), 10, &community(), None);
+    }
+}
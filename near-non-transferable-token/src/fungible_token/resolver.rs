@@ -1,5 +1,8 @@
-use near_sdk::{ext_contract, json_types::U128, AccountId};
+use near_sdk::{ext_contract, json_types::{U128, U64}, AccountId};
 
+/// Resolves the `_call` methods of [`FungibleTokenCore`](crate::fungible_token::core::FungibleTokenCore).
+/// Every resolver returns the amount the receiver used, what it was sent minus what went back
+/// to the owner.
 #[ext_contract(ext_ft_resolver)]
 pub trait FungibleTokenResolver {
 
@@ -11,17 +14,31 @@ pub trait FungibleTokenResolver {
         amount: U128,
     ) -> U128;
 
+    fn ft_resolve_batch_deposit(
+        &mut self,
+        owner_id: AccountId,
+        receiver_ids: Vec<AccountId>,
+        contract_id: AccountId,
+        amounts: Vec<U128>,
+    ) -> Vec<U128>;
+
+    /// `expires_at` is the expiry the deposit had before the withdrawal, kept for the unused
+    /// amount that is locked again. `freed_storage_usage` is the storage the withdrawal freed,
+    /// refunded to the owner once the unused amount is locked again.
     fn ft_resolve_withdraw(
         &mut self,
         owner_id: AccountId,
         receiver_id: AccountId,
         contract_id: AccountId,
         amount: U128,
+        expires_at: Option<U64>,
+        freed_storage_usage: U64,
     ) -> U128;
 
     fn ft_resolve_burn(
         &mut self,
         owner_id: AccountId,
+        receiver_id: AccountId,
         contract_id: AccountId,
         amount: U128,
     ) -> U128;
@@ -1,13 +1,14 @@
 use crate::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
 use near_sdk::json_types::U128;
-use near_sdk::{assert_one_yocto, env, log, AccountId, Balance, Promise};
+use near_sdk::{assert_one_yocto, env, log, require, AccountId, Balance, Promise, StorageUsage};
 
 use crate::fungible_token::core_impl::FungibleToken;
+use crate::fungible_token::events::StorageChanged;
 
 impl FungibleToken {
     fn internal_storage_balance_of(&self, account_id: &AccountId, include_deposit_contracts: bool) -> Option<StorageBalance> {
         if self.accounts.contains_key(account_id) {
-            let account = self.accounts.get(&account_id).unwrap();
+            let account = self.internal_unwrap_account(account_id);
             let mut contract_count = account.contract_ids.len() - 1;
             if include_deposit_contracts {
                 contract_count += account.deposit_map.len();
@@ -18,6 +19,49 @@ impl FungibleToken {
             None
         }
     }
+
+    /// Charges the predecessor for the storage grown since `initial_storage_usage` out of the
+    /// attached deposit, adds the charge to what it paid for storage, and refunds the rest.
+    pub fn internal_settle_storage(&mut self, initial_storage_usage: StorageUsage) {
+        if env::storage_usage() > initial_storage_usage {
+            let account_id = env::predecessor_account_id();
+            let paid = self.storage_paid.get(&account_id).unwrap_or(0);
+            // Written first so that the charge covers the entry itself, whose size does not change.
+            self.storage_paid.insert(&account_id, &paid);
+            let charged = Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+            self.storage_paid.insert(&account_id, &(paid + charged));
+        }
+        settle_storage_deposit(initial_storage_usage)
+    }
+
+    /// Refunds `account_id` for `freed_storage_usage` bytes, up to what it paid for storage and
+    /// was not refunded yet.
+    pub fn internal_refund_storage(&mut self, account_id: &AccountId, freed_storage_usage: StorageUsage) {
+        let paid = self.storage_paid.get(account_id).unwrap_or(0);
+        let refund = (Balance::from(freed_storage_usage) * env::storage_byte_cost()).min(paid);
+        if refund > 0 {
+            self.storage_paid.insert(account_id, &(paid - refund));
+            Promise::new(account_id.clone()).transfer(refund);
+        }
+    }
+}
+
+/// See [`FungibleToken::internal_settle_storage`].
+pub(crate) fn settle_storage_deposit(initial_storage_usage: StorageUsage) {
+    let amount: Balance = env::attached_deposit();
+    let required_storage_balance =
+        Balance::from(env::storage_usage().saturating_sub(initial_storage_usage)) * env::storage_byte_cost();
+    require!(
+        amount >= required_storage_balance,
+        format!("The attached deposit is less than the required storage balance of {}", required_storage_balance)
+    );
+    if required_storage_balance > 0 {
+        StorageChanged { account_id: &env::predecessor_account_id(), amount: &required_storage_balance.into(), memo: None }.emit();
+    }
+    let refund = amount - required_storage_balance;
+    if refund > 0 {
+        Promise::new(env::predecessor_account_id()).transfer(refund);
+    }
 }
 
 impl StorageManagement for FungibleToken {
@@ -42,6 +86,7 @@ impl StorageManagement for FungibleToken {
             }
 
             self.internal_register_account(&account_id);
+            StorageChanged { account_id: &account_id, amount: &min_balance.into(), memo: None }.emit();
             let refund = amount - min_balance;
             if refund > 0 {
                 Promise::new(env::predecessor_account_id()).transfer(refund);
@@ -73,6 +118,7 @@ impl StorageManagement for FungibleToken {
         }
     }
 
+    #[allow(unused_variables)]
     fn storage_unregister(&mut self, force: Option<bool>) -> bool {
         false
     }
@@ -1,10 +1,25 @@
+#[cfg(any(feature = "contract", feature = "replay"))]
+#[macro_use]
+mod ntt_events;
+
 /// Fungible tokens as described in [by the spec](https://nomicon.io/Standards/FungibleToken/README.html).
+#[cfg(feature = "contract")]
 pub mod fungible_token;
-/// Non-fungible tokens as described in [by the spec](https://nomicon.io/Standards/NonFungibleToken/README.html).
-// pub mod non_fungible_token;
+/// Soulbound non-fungible tokens, as described in [by the spec](https://nomicon.io/Standards/NonFungibleToken/README.html) but without transfers.
+#[cfg(feature = "contract")]
+pub mod non_fungible_token;
 /// Storage management deals with handling [state storage](https://docs.near.org/docs/concepts/storage-staking) on NEAR. This follows the [storage management standard](https://nomicon.io/Standards/StorageManagement.html).
+#[cfg(feature = "contract")]
 pub mod storage_management;
-/// This upgrade standard is a use case where a staging area exists for a WASM
-/// blob, allowing it to be stored for a period of time before deployed.
+/// Roles held globally or per community, to guard the methods of a contract.
+#[cfg(feature = "contract")]
+pub mod access_control;
+/// Decoding of the logged events and replay of the balances they describe, for off-chain services.
+#[cfg(feature = "replay")]
+pub mod replay;
 
+#[cfg(feature = "contract")]
 pub(crate) mod event;
+
+#[cfg(all(test, feature = "contract"))]
+pub(crate) mod test_utils;
@@ -0,0 +1,37 @@
+use near_sdk::json_types::U128;
+use near_sdk::{ext_contract, AccountId};
+
+use crate::non_fungible_token::metadata::{Token, TokenId, TokenMetadata};
+
+/// Soulbound badges: they are issued in a community, can be revoked by its issuers or burned by
+/// the owner, and can never be transferred.
+#[ext_contract(ext_sbt_core)]
+pub trait SoulboundTokenCore {
+    /// Issues a badge of the community `contract_id` to `receiver_id`. The caller has to be an
+    /// issuer of the community and attach a deposit to cover the storage.
+    fn nft_mint(&mut self, contract_id: AccountId, receiver_id: AccountId, metadata: TokenMetadata) -> Token;
+
+    /// Removes a badge. The caller has to be an issuer of the badge's community, with 1 yoctoNEAR attached.
+    fn nft_revoke(&mut self, token_id: TokenId, memo: Option<String>);
+
+    /// Removes one of the caller's badges. Requires 1 yoctoNEAR attached.
+    fn nft_burn(&mut self, token_id: TokenId, memo: Option<String>);
+
+    fn nft_token(&self, token_id: TokenId) -> Option<Token>;
+}
+
+/// The accounts that can issue the badges of a community. The community account is not one
+/// until it adds itself.
+#[ext_contract(ext_sbt_issuers)]
+pub trait SoulboundTokenIssuers {
+    /// Lets `issuer_id` issue badges of the caller's community.
+    /// Requires a deposit to cover the added storage.
+    fn nft_add_issuer(&mut self, issuer_id: AccountId);
+
+    /// Requires 1 yoctoNEAR attached.
+    fn nft_remove_issuer(&mut self, issuer_id: AccountId);
+
+    fn nft_is_issuer(&self, contract_id: AccountId, account_id: AccountId) -> bool;
+
+    fn nft_issuers(&self, contract_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<AccountId>;
+}
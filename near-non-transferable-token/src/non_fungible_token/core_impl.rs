@@ -0,0 +1,321 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, require, AccountId, IntoStorageKey};
+
+use crate::fungible_token::events::{RoleGranted, RoleRevoked};
+use crate::fungible_token::storage_impl::settle_storage_deposit;
+use crate::non_fungible_token::core::{SoulboundTokenCore, SoulboundTokenIssuers};
+use crate::non_fungible_token::events::{NftBurn, NftMint};
+use crate::non_fungible_token::metadata::{Token, TokenId, TokenMetadata};
+
+/// The role of the issuers in the `role_granted` and `role_revoked` events.
+pub const ISSUER_ROLE: &str = "issuer";
+
+/// Implementation of soulbound badges, issued per community.
+/// Meant to live next to `FungibleToken` in the same contract, with a prefix that none of its
+/// keys start with.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct SoulboundToken {
+    prefix: Vec<u8>,
+
+    /// TokenId -> Token.
+    pub tokens: UnorderedMap<TokenId, Token>,
+
+    /// Owner -> ids of the badges they hold.
+    pub tokens_per_owner: LookupMap<AccountId, UnorderedSet<TokenId>>,
+
+    /// Community -> ids of the badges issued in it.
+    pub tokens_per_community: LookupMap<AccountId, UnorderedSet<TokenId>>,
+
+    /// Community -> accounts it lets issue its badges.
+    pub issuers: LookupMap<AccountId, UnorderedSet<AccountId>>,
+
+    /// The id of the next badge.
+    pub next_token_id: u64,
+}
+
+impl SoulboundToken {
+    pub fn new<S>(prefix: S) -> Self
+    where
+        S: IntoStorageKey,
+    {
+        let prefix = prefix.into_storage_key();
+        Self {
+            tokens: UnorderedMap::new([prefix.as_slice(), b"t"].concat()),
+            tokens_per_owner: LookupMap::new([prefix.as_slice(), b"o"].concat()),
+            tokens_per_community: LookupMap::new([prefix.as_slice(), b"c"].concat()),
+            issuers: LookupMap::new([prefix.as_slice(), b"i"].concat()),
+            next_token_id: 0,
+            prefix,
+        }
+    }
+
+    fn nested_prefix(&self, kind: &[u8], account_id: &AccountId) -> Vec<u8> {
+        [self.prefix.as_slice(), kind, b":", account_id.as_bytes()].concat()
+    }
+
+    /// Whether `contract_id` added `account_id` to its issuers, itself included.
+    pub fn is_issuer(&self, contract_id: &AccountId, account_id: &AccountId) -> bool {
+        match self.issuers.get(contract_id) {
+            Some(issuers) => issuers.contains(account_id),
+            None => false
+        }
+    }
+
+    pub fn internal_unwrap_token(&self, token_id: &TokenId) -> Token {
+        self.tokens
+            .get(token_id)
+            .unwrap_or_else(|| env::panic_str(format!("The token {} does not exist", token_id).as_str()))
+    }
+
+    /// Issues a badge without checking the caller or charging for storage.
+    pub fn internal_mint(&mut self, contract_id: &AccountId, receiver_id: &AccountId, metadata: TokenMetadata, memo: Option<&str>) -> Token {
+        let token = self.internal_insert_token(contract_id, receiver_id, metadata);
+        NftMint { owner_id: receiver_id, token_ids: &[&token.token_id], memo }.emit();
+        token
+    }
+
+    /// Like [`internal_mint`](Self::internal_mint), without logging `nft_mint`. For callers that
+    /// log the mint in another standard.
+    pub fn internal_insert_token(&mut self, contract_id: &AccountId, receiver_id: &AccountId, metadata: TokenMetadata) -> Token {
+        metadata.assert_valid();
+        let token_id = self.next_token_id.to_string();
+        self.next_token_id += 1;
+        let token = Token {
+            token_id: token_id.clone(),
+            owner_id: receiver_id.clone(),
+            contract_id: contract_id.clone(),
+            metadata,
+        };
+        self.tokens.insert(&token_id, &token);
+        self.internal_add_token_to_owner(receiver_id, &token_id);
+        let mut community_tokens = self
+            .tokens_per_community
+            .get(contract_id)
+            .unwrap_or_else(|| UnorderedSet::new(self.nested_prefix(b"c", contract_id)));
+        community_tokens.insert(&token_id);
+        self.tokens_per_community.insert(contract_id, &community_tokens);
+        token
+    }
+
+    /// Removes a badge without checking the caller. `authorized_id` is logged as the one who
+    /// removed it on behalf of the owner.
+    pub fn internal_burn(&mut self, token_id: &TokenId, authorized_id: Option<&AccountId>, memo: Option<&str>) -> Token {
+        let token = self.internal_remove_token(token_id);
+        NftBurn { owner_id: &token.owner_id, token_ids: &[token_id], authorized_id, memo }.emit();
+        token
+    }
+
+    /// Like [`internal_burn`](Self::internal_burn), without logging `nft_burn`.
+    pub fn internal_remove_token(&mut self, token_id: &TokenId) -> Token {
+        let token = self.internal_unwrap_token(token_id);
+        self.tokens.remove(token_id);
+        self.internal_remove_token_from_owner(&token.owner_id, token_id);
+        if let Some(mut community_tokens) = self.tokens_per_community.get(&token.contract_id) {
+            community_tokens.remove(token_id);
+            if community_tokens.is_empty() {
+                self.tokens_per_community.remove(&token.contract_id);
+            } else {
+                self.tokens_per_community.insert(&token.contract_id, &community_tokens);
+            }
+        }
+        token
+    }
+
+    /// Moves the badges of the community `contract_id` from `owner_id` to `receiver_id`
+    /// without checking the caller. Returns the ids of the moved badges.
+    pub fn internal_recover(&mut self, contract_id: &AccountId, owner_id: &AccountId, receiver_id: &AccountId) -> Vec<TokenId> {
+        let token_ids: Vec<TokenId> = match self.tokens_per_owner.get(owner_id) {
+            Some(token_ids) => token_ids.iter().collect(),
+            None => return vec![]
+        };
+        let mut moved = vec![];
+        for token_id in token_ids {
+            let mut token = self.internal_unwrap_token(&token_id);
+            if &token.contract_id != contract_id {
+                continue;
+            }
+            token.owner_id = receiver_id.clone();
+            self.tokens.insert(&token_id, &token);
+            self.internal_remove_token_from_owner(owner_id, &token_id);
+            self.internal_add_token_to_owner(receiver_id, &token_id);
+            moved.push(token_id);
+        }
+        moved
+    }
+
+    pub(crate) fn internal_add_token_to_owner(&mut self, owner_id: &AccountId, token_id: &TokenId) {
+        let mut owner_tokens = self
+            .tokens_per_owner
+            .get(owner_id)
+            .unwrap_or_else(|| UnorderedSet::new(self.nested_prefix(b"o", owner_id)));
+        owner_tokens.insert(token_id);
+        self.tokens_per_owner.insert(owner_id, &owner_tokens);
+    }
+
+    pub(crate) fn internal_remove_token_from_owner(&mut self, owner_id: &AccountId, token_id: &TokenId) {
+        if let Some(mut owner_tokens) = self.tokens_per_owner.get(owner_id) {
+            owner_tokens.remove(token_id);
+            if owner_tokens.is_empty() {
+                self.tokens_per_owner.remove(owner_id);
+            } else {
+                self.tokens_per_owner.insert(owner_id, &owner_tokens);
+            }
+        }
+    }
+}
+
+impl SoulboundTokenCore for SoulboundToken {
+    fn nft_mint(&mut self, contract_id: AccountId, receiver_id: AccountId, metadata: TokenMetadata) -> Token {
+        let initial_storage_usage = env::storage_usage();
+        require!(self.is_issuer(&contract_id, &env::predecessor_account_id()), "Only an issuer of the community can mint");
+        let token = self.internal_mint(&contract_id, &receiver_id, metadata, None);
+        settle_storage_deposit(initial_storage_usage);
+        token
+    }
+
+    fn nft_revoke(&mut self, token_id: TokenId, memo: Option<String>) {
+        assert_one_yocto();
+        let issuer_id = env::predecessor_account_id();
+        let token = self.internal_unwrap_token(&token_id);
+        require!(self.is_issuer(&token.contract_id, &issuer_id), "Only an issuer of the community can revoke");
+        self.internal_burn(&token_id, Some(&issuer_id), memo.as_deref());
+    }
+
+    fn nft_burn(&mut self, token_id: TokenId, memo: Option<String>) {
+        assert_one_yocto();
+        let token = self.internal_unwrap_token(&token_id);
+        require!(token.owner_id == env::predecessor_account_id(), "Only the owner can burn");
+        self.internal_burn(&token_id, None, memo.as_deref());
+    }
+
+    fn nft_token(&self, token_id: TokenId) -> Option<Token> {
+        self.tokens.get(&token_id)
+    }
+}
+
+impl SoulboundTokenIssuers for SoulboundToken {
+    fn nft_add_issuer(&mut self, issuer_id: AccountId) {
+        let initial_storage_usage = env::storage_usage();
+        let contract_id = env::predecessor_account_id();
+        let mut issuers = self
+            .issuers
+            .get(&contract_id)
+            .unwrap_or_else(|| UnorderedSet::new(self.nested_prefix(b"i", &contract_id)));
+        if issuers.insert(&issuer_id) {
+            self.issuers.insert(&contract_id, &issuers);
+            RoleGranted { role: ISSUER_ROLE, account_id: &issuer_id, sender_id: &contract_id, contract_id: Some(&contract_id) }.emit();
+        }
+        settle_storage_deposit(initial_storage_usage);
+    }
+
+    fn nft_remove_issuer(&mut self, issuer_id: AccountId) {
+        assert_one_yocto();
+        let contract_id = env::predecessor_account_id();
+        if let Some(mut issuers) = self.issuers.get(&contract_id) {
+            if issuers.remove(&issuer_id) {
+                if issuers.is_empty() {
+                    self.issuers.remove(&contract_id);
+                } else {
+                    self.issuers.insert(&contract_id, &issuers);
+                }
+                RoleRevoked { role: ISSUER_ROLE, account_id: &issuer_id, sender_id: &contract_id, contract_id: Some(&contract_id) }.emit();
+            }
+        }
+    }
+
+    fn nft_is_issuer(&self, contract_id: AccountId, account_id: AccountId) -> bool {
+        self.is_issuer(&contract_id, &account_id)
+    }
+
+    fn nft_issuers(&self, contract_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<AccountId> {
+        match self.issuers.get(&contract_id) {
+            Some(issuers) => issuers
+                .iter()
+                .skip(from_index.map_or(0, |index| index.0 as usize))
+                .take(limit.map_or(usize::MAX, |limit| limit as usize))
+                .collect(),
+            None => vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::non_fungible_token::enumeration::SoulboundTokenEnumeration;
+    use crate::test_utils::{community, set_caller};
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::ONE_NEAR;
+
+    fn setup() -> SoulboundToken {
+        set_caller(community(), ONE_NEAR);
+        let mut badges = SoulboundToken::new(b"sbt".to_vec());
+        badges.nft_add_issuer(community());
+        badges.nft_add_issuer(accounts(0));
+        badges
+    }
+
+    #[test]
+    fn mint_and_enumerate() {
+        let mut badges = setup();
+        set_caller(accounts(0), ONE_NEAR);
+        badges.nft_mint(community(), accounts(1), TokenMetadata::default());
+        badges.nft_mint(community(), accounts(2), TokenMetadata::default());
+        assert!(get_logs()[0].contains(r#""event":"nft_mint","data":[{"owner_id":"bob","token_ids":["0"]}]"#));
+        assert_eq!(badges.nft_total_supply().0, 2);
+        assert_eq!(badges.nft_supply_for_community(community()).0, 2);
+        assert_eq!(badges.nft_tokens_for_owner(accounts(1), None, None)[0].token_id, "0");
+        assert_eq!(badges.nft_tokens_for_community(community(), Some(U128(1)), Some(1))[0].owner_id, accounts(2));
+    }
+
+    #[test]
+    fn revoke_and_burn() {
+        let mut badges = setup();
+        badges.nft_mint(community(), accounts(1), TokenMetadata::default());
+        badges.nft_mint(community(), accounts(1), TokenMetadata::default());
+        set_caller(accounts(0), 1);
+        badges.nft_revoke("0".to_string(), None);
+        assert!(get_logs()[0].contains(r#""authorized_id":"alice""#));
+        set_caller(accounts(1), 1);
+        badges.nft_burn("1".to_string(), None);
+        assert_eq!(badges.nft_supply_for_owner(accounts(1)).0, 0);
+        assert_eq!(badges.nft_supply_for_community(community()).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only an issuer of the community can mint")]
+    fn mint_not_issuer() {
+        let mut badges = setup();
+        set_caller(accounts(1), ONE_NEAR);
+        badges.nft_mint(community(), accounts(1), TokenMetadata::default());
+    }
+
+    #[test]
+    fn issuer_events() {
+        let mut badges = setup();
+        assert!(get_logs().iter().any(|log| log.contains(r#""event":"role_granted","data":[{"role":"issuer","account_id":"alice","sender_id":"community","contract_id":"community"}]"#)));
+        set_caller(community(), 1);
+        badges.nft_remove_issuer(community());
+        assert!(get_logs()[0].contains(r#""event":"role_revoked","data":[{"role":"issuer","account_id":"community","sender_id":"community","contract_id":"community"}]"#));
+        assert!(!badges.nft_is_issuer(community(), community()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only an issuer of the community can mint")]
+    fn mint_by_community_not_issuer() {
+        set_caller(community(), ONE_NEAR);
+        let mut badges = SoulboundToken::new(b"sbt".to_vec());
+        badges.nft_mint(community(), accounts(1), TokenMetadata::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can burn")]
+    fn burn_not_owner() {
+        let mut badges = setup();
+        badges.nft_mint(community(), accounts(1), TokenMetadata::default());
+        set_caller(accounts(0), 1);
+        badges.nft_burn("0".to_string(), None);
+    }
+}
@@ -0,0 +1,65 @@
+use near_sdk::collections::UnorderedSet;
+use near_sdk::json_types::U128;
+use near_sdk::{ext_contract, AccountId};
+
+use crate::non_fungible_token::core_impl::SoulboundToken;
+use crate::non_fungible_token::metadata::{Token, TokenId};
+
+#[ext_contract(ext_sbt_enumeration)]
+pub trait SoulboundTokenEnumeration {
+    fn nft_total_supply(&self) -> U128;
+
+    fn nft_tokens(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token>;
+
+    fn nft_supply_for_owner(&self, account_id: AccountId) -> U128;
+
+    fn nft_tokens_for_owner(&self, account_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token>;
+
+    fn nft_supply_for_community(&self, contract_id: AccountId) -> U128;
+
+    fn nft_tokens_for_community(&self, contract_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token>;
+}
+
+impl SoulboundToken {
+    fn internal_tokens_of(&self, token_ids: Option<UnorderedSet<TokenId>>, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+        match token_ids {
+            Some(token_ids) => token_ids
+                .iter()
+                .skip(from_index.map_or(0, |index| index.0 as usize))
+                .take(limit.map_or(usize::MAX, |limit| limit as usize))
+                .filter_map(|token_id| self.tokens.get(&token_id))
+                .collect(),
+            None => vec![]
+        }
+    }
+}
+
+impl SoulboundTokenEnumeration for SoulboundToken {
+    fn nft_total_supply(&self) -> U128 {
+        (self.tokens.len() as u128).into()
+    }
+
+    fn nft_tokens(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+        self.tokens
+            .values()
+            .skip(from_index.map_or(0, |index| index.0 as usize))
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect()
+    }
+
+    fn nft_supply_for_owner(&self, account_id: AccountId) -> U128 {
+        self.tokens_per_owner.get(&account_id).map_or(0, |token_ids| token_ids.len() as u128).into()
+    }
+
+    fn nft_tokens_for_owner(&self, account_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+        self.internal_tokens_of(self.tokens_per_owner.get(&account_id), from_index, limit)
+    }
+
+    fn nft_supply_for_community(&self, contract_id: AccountId) -> U128 {
+        self.tokens_per_community.get(&contract_id).map_or(0, |token_ids| token_ids.len() as u128).into()
+    }
+
+    fn nft_tokens_for_community(&self, contract_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+        self.internal_tokens_of(self.tokens_per_community.get(&contract_id), from_index, limit)
+    }
+}
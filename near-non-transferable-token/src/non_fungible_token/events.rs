@@ -0,0 +1,227 @@
+//! Standard for nep171 (Non-Fungible Token) events, as logged by the soulbound badges.
+//!
+//! These events will be picked up by the NEAR indexer.
+//!
+//! <https://github.com/near/NEPs/blob/master/specs/Standards/NonFungibleToken/Event.md>
+//!
+//! Badges cannot be transferred, so only [`NftMint`] and [`NftBurn`] are logged. A revoked badge
+//! is logged as a burn with the issuer as `authorized_id`.
+//!
+//! The NEP-393 registry methods also log [`SbtMint`], [`SbtRecover`], [`SbtRevoke`] and
+//! [`SbtBurn`] under the `nep393` standard, in place of the `nep171` events:
+//! <https://github.com/near/NEPs/blob/master/neps/nep-0393.md>
+//!
+//! These events can be logged by calling `.emit()` on them if a single event, or calling
+//! `emit_many` on the event type. The `nep393` events hold a single event each.
+
+use crate::event::NearEvent;
+use near_sdk::AccountId;
+use serde::Serialize;
+
+/// Data to log for an NFT mint event. To log this event, call [`.emit()`](NftMint::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct NftMint<'a> {
+    pub owner_id: &'a AccountId,
+    pub token_ids: &'a [&'a str],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+impl NftMint<'_> {
+    /// Logs the event to the host. This is required to ensure that the event is triggered
+    /// and to consume the event.
+    pub fn emit(self) {
+        Self::emit_many(&[self])
+    }
+
+    /// Emits an nft mint event, through [`env::log_str`](near_sdk::env::log_str),
+    /// where each [`NftMint`] represents the data of each mint.
+    pub fn emit_many(data: &[NftMint<'_>]) {
+        new_171_v1(Nep171EventKind::NftMint(data)).emit()
+    }
+}
+
+/// Data to log for an NFT burn event. To log this event, call [`.emit()`](NftBurn::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct NftBurn<'a> {
+    pub owner_id: &'a AccountId,
+    pub token_ids: &'a [&'a str],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<&'a AccountId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+impl NftBurn<'_> {
+    /// Logs the event to the host. This is required to ensure that the event is triggered
+    /// and to consume the event.
+    pub fn emit(self) {
+        Self::emit_many(&[self])
+    }
+
+    /// Emits an nft burn event, through [`env::log_str`](near_sdk::env::log_str),
+    /// where each [`NftBurn`] represents the data of each burn.
+    pub fn emit_many<'a>(data: &'a [NftBurn<'a>]) {
+        new_171_v1(Nep171EventKind::NftBurn(data)).emit()
+    }
+}
+
+/// Data to log for an SBT mint event of `issuer`, with the token ids minted for each owner.
+/// To log this event, call [`.emit()`](SbtMint::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct SbtMint<'a> {
+    pub issuer: &'a AccountId,
+    pub tokens: &'a [(&'a AccountId, &'a [u64])],
+}
+
+impl SbtMint<'_> {
+    pub fn emit(self) {
+        new_393_v1(Nep393EventKind::Mint(self)).emit()
+    }
+}
+
+/// Data to log for an SBT recover event, when `issuer` moved its tokens from `old_owner` to
+/// `new_owner`. To log this event, call [`.emit()`](SbtRecover::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct SbtRecover<'a> {
+    pub issuer: &'a AccountId,
+    pub old_owner: &'a AccountId,
+    pub new_owner: &'a AccountId,
+    pub tokens: &'a [u64],
+}
+
+impl SbtRecover<'_> {
+    pub fn emit(self) {
+        new_393_v1(Nep393EventKind::Recover(self)).emit()
+    }
+}
+
+/// Data to log for an SBT revoke event. To log this event, call [`.emit()`](SbtRevoke::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct SbtRevoke<'a> {
+    pub issuer: &'a AccountId,
+    pub tokens: &'a [u64],
+}
+
+impl SbtRevoke<'_> {
+    pub fn emit(self) {
+        new_393_v1(Nep393EventKind::Revoke(self)).emit()
+    }
+}
+
+/// Data to log for an SBT burn event. To log this event, call [`.emit()`](SbtBurn::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct SbtBurn<'a> {
+    pub issuer: &'a AccountId,
+    pub tokens: &'a [u64],
+}
+
+impl SbtBurn<'_> {
+    pub fn emit(self) {
+        new_393_v1(Nep393EventKind::Burn(self)).emit()
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct Nep171Event<'a> {
+    version: &'static str,
+    #[serde(flatten)]
+    event_kind: Nep171EventKind<'a>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)]
+enum Nep171EventKind<'a> {
+    NftMint(&'a [NftMint<'a>]),
+    NftBurn(&'a [NftBurn<'a>]),
+}
+
+fn new_171<'a>(version: &'static str, event_kind: Nep171EventKind<'a>) -> NearEvent<'a> {
+    NearEvent::Nep171(Nep171Event { version, event_kind })
+}
+
+fn new_171_v1(event_kind: Nep171EventKind) -> NearEvent {
+    new_171("1.0.0", event_kind)
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct Nep393Event<'a> {
+    version: &'static str,
+    #[serde(flatten)]
+    event_kind: Nep393EventKind<'a>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+enum Nep393EventKind<'a> {
+    Mint(SbtMint<'a>),
+    Recover(SbtRecover<'a>),
+    Revoke(SbtRevoke<'a>),
+    Burn(SbtBurn<'a>),
+}
+
+fn new_393_v1(event_kind: Nep393EventKind) -> NearEvent {
+    NearEvent::Nep393(Nep393Event { version: "1.0.0", event_kind })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::{test_utils, AccountId};
+
+    fn bob() -> AccountId {
+        AccountId::new_unchecked("bob".to_string())
+    }
+
+    fn issuer() -> AccountId {
+        AccountId::new_unchecked("issuer".to_string())
+    }
+
+    #[test]
+    fn nft_mint() {
+        NftMint { owner_id: &bob(), token_ids: &["0", "1"], memo: None }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{"owner_id":"bob","token_ids":["0","1"]}]}"#
+        );
+    }
+
+    #[test]
+    fn nft_burns() {
+        NftBurn::emit_many(&[
+            NftBurn { owner_id: &bob(), token_ids: &["0"], authorized_id: None, memo: None },
+            NftBurn { owner_id: &bob(), token_ids: &["1"], authorized_id: Some(&issuer()), memo: Some("revoked") },
+        ]);
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_burn","data":[{"owner_id":"bob","token_ids":["0"]},{"owner_id":"bob","token_ids":["1"],"authorized_id":"issuer","memo":"revoked"}]}"#
+        );
+    }
+
+    #[test]
+    fn sbt_mint() {
+        SbtMint { issuer: &issuer(), tokens: &[(&bob(), &[0, 1])] }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"nep393","version":"1.0.0","event":"mint","data":{"issuer":"issuer","tokens":[["bob",[0,1]]]}}"#
+        );
+    }
+
+    #[test]
+    fn sbt_recover() {
+        SbtRecover { issuer: &issuer(), old_owner: &bob(), new_owner: &issuer(), tokens: &[3] }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"nep393","version":"1.0.0","event":"recover","data":{"issuer":"issuer","old_owner":"bob","new_owner":"issuer","tokens":[3]}}"#
+        );
+    }
+}
@@ -0,0 +1,128 @@
+/// The methods of the soulbound badges: minting, revoking and burning, the issuers of each
+/// community and the enumeration views.
+/// Takes name of the Contract struct and the inner field for the badges.
+#[macro_export]
+macro_rules! impl_soulbound_token {
+    ($contract: ident, $badges: ident) => {
+
+        #[near_bindgen]
+        impl SoulboundTokenCore for $contract {
+
+            #[payable]
+            fn nft_mint(&mut self, contract_id: AccountId, receiver_id: AccountId, metadata: TokenMetadata) -> Token {
+                self.$badges.nft_mint(contract_id, receiver_id, metadata)
+            }
+
+            #[payable]
+            fn nft_revoke(&mut self, token_id: TokenId, memo: Option<String>) {
+                self.$badges.nft_revoke(token_id, memo)
+            }
+
+            #[payable]
+            fn nft_burn(&mut self, token_id: TokenId, memo: Option<String>) {
+                self.$badges.nft_burn(token_id, memo)
+            }
+
+            fn nft_token(&self, token_id: TokenId) -> Option<Token> {
+                self.$badges.nft_token(token_id)
+            }
+        }
+
+        #[near_bindgen]
+        impl SoulboundTokenIssuers for $contract {
+
+            #[payable]
+            fn nft_add_issuer(&mut self, issuer_id: AccountId) {
+                self.$badges.nft_add_issuer(issuer_id)
+            }
+
+            #[payable]
+            fn nft_remove_issuer(&mut self, issuer_id: AccountId) {
+                self.$badges.nft_remove_issuer(issuer_id)
+            }
+
+            fn nft_is_issuer(&self, contract_id: AccountId, account_id: AccountId) -> bool {
+                self.$badges.nft_is_issuer(contract_id, account_id)
+            }
+
+            fn nft_issuers(&self, contract_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<AccountId> {
+                self.$badges.nft_issuers(contract_id, from_index, limit)
+            }
+        }
+
+        #[near_bindgen]
+        impl SoulboundTokenEnumeration for $contract {
+
+            fn nft_total_supply(&self) -> U128 {
+                self.$badges.nft_total_supply()
+            }
+
+            fn nft_tokens(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+                self.$badges.nft_tokens(from_index, limit)
+            }
+
+            fn nft_supply_for_owner(&self, account_id: AccountId) -> U128 {
+                self.$badges.nft_supply_for_owner(account_id)
+            }
+
+            fn nft_tokens_for_owner(&self, account_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+                self.$badges.nft_tokens_for_owner(account_id, from_index, limit)
+            }
+
+            fn nft_supply_for_community(&self, contract_id: AccountId) -> U128 {
+                self.$badges.nft_supply_for_community(contract_id)
+            }
+
+            fn nft_tokens_for_community(&self, contract_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<Token> {
+                self.$badges.nft_tokens_for_community(contract_id, from_index, limit)
+            }
+        }
+    };
+}
+
+/// Exposes the badges through the NEP-393 soulbound token registry interface, with each
+/// community as an issuer.
+/// Takes name of the Contract struct and the inner field for the badges.
+#[macro_export]
+macro_rules! impl_soulbound_token_registry {
+    ($contract: ident, $badges: ident) => {
+
+        #[near_bindgen]
+        impl SoulboundTokenRegistry for $contract {
+
+            #[payable]
+            fn sbt_mint(&mut self, token_spec: Vec<(AccountId, Vec<SbtTokenMetadata>)>) -> Vec<SbtTokenId> {
+                self.$badges.sbt_mint(token_spec)
+            }
+
+            #[payable]
+            fn sbt_recover(&mut self, owner: AccountId, recipient: AccountId) {
+                self.$badges.sbt_recover(owner, recipient)
+            }
+
+            #[payable]
+            fn sbt_revoke(&mut self, tokens: Vec<SbtTokenId>, burn: bool) {
+                self.$badges.sbt_revoke(tokens, burn)
+            }
+
+            fn sbt(&self, issuer: AccountId, token: SbtTokenId) -> Option<SbtToken> {
+                self.$badges.sbt(issuer, token)
+            }
+
+            fn sbt_supply(&self, issuer: AccountId) -> u64 {
+                self.$badges.sbt_supply(issuer)
+            }
+
+            fn sbt_tokens_by_owner(
+                &self,
+                account: AccountId,
+                issuer: Option<AccountId>,
+                from_class: Option<u64>,
+                limit: Option<u32>,
+                with_expired: Option<bool>,
+            ) -> Vec<(AccountId, Vec<SbtOwnedToken>)> {
+                self.$badges.sbt_tokens_by_owner(account, issuer, from_class, limit, with_expired)
+            }
+        }
+    };
+}
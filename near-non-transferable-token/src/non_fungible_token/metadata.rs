@@ -0,0 +1,47 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{require, AccountId};
+
+/// Token ids are assigned in order of minting, starting from `"0"`.
+pub type TokenId = String;
+
+/// The metadata of a badge, following NEP-177.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Deserialize, Serialize, Debug, PartialEq, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub media: Option<String>,
+    pub media_hash: Option<Base64VecU8>,
+    pub issued_at: Option<U64>,
+    pub expires_at: Option<U64>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+    /// The NEP-393 class of the badge.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<u64>,
+}
+
+impl TokenMetadata {
+    pub fn assert_valid(&self) {
+        require!(self.media.is_some() == self.media_hash.is_some());
+        if let Some(media_hash) = &self.media_hash {
+            require!(media_hash.0.len() == 32, "Media hash has to be 32 bytes");
+        }
+        require!(self.reference.is_some() == self.reference_hash.is_some());
+        if let Some(reference_hash) = &self.reference_hash {
+            require!(reference_hash.0.len() == 32, "Hash has to be 32 bytes");
+        }
+    }
+}
+
+/// A badge held by `owner_id`, issued in the community `contract_id`.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Deserialize, Serialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Token {
+    pub token_id: TokenId,
+    pub owner_id: AccountId,
+    pub contract_id: AccountId,
+    pub metadata: TokenMetadata,
+}
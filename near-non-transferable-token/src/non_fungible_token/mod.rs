@@ -0,0 +1,9 @@
+pub mod core;
+pub mod core_impl;
+pub mod enumeration;
+pub mod events;
+pub mod macros;
+pub mod metadata;
+pub mod sbt;
+
+pub use core_impl::SoulboundToken;
@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{assert_one_yocto, env, ext_contract, require, AccountId};
+
+use crate::fungible_token::storage_impl::settle_storage_deposit;
+use crate::non_fungible_token::core_impl::SoulboundToken;
+use crate::non_fungible_token::events::{SbtBurn, SbtMint, SbtRecover, SbtRevoke};
+use crate::non_fungible_token::metadata::{Token, TokenMetadata};
+
+/// The NEP-393 id of a badge: its `token_id` as a number.
+pub type SbtTokenId = u64;
+pub type ClassId = u64;
+
+/// The token metadata of NEP-393.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SbtTokenMetadata {
+    pub class: ClassId,
+    pub issued_at: Option<u64>,
+    pub expires_at: Option<u64>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SbtToken {
+    pub token: SbtTokenId,
+    pub owner: AccountId,
+    pub metadata: SbtTokenMetadata,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SbtOwnedToken {
+    pub token: SbtTokenId,
+    pub metadata: SbtTokenMetadata,
+}
+
+impl From<SbtTokenMetadata> for TokenMetadata {
+    fn from(metadata: SbtTokenMetadata) -> Self {
+        Self {
+            issued_at: metadata.issued_at.map(Into::into),
+            expires_at: metadata.expires_at.map(Into::into),
+            reference: metadata.reference,
+            reference_hash: metadata.reference_hash,
+            class: Some(metadata.class),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<TokenMetadata> for SbtTokenMetadata {
+    fn from(metadata: TokenMetadata) -> Self {
+        Self {
+            class: metadata.class.unwrap_or(0),
+            issued_at: metadata.issued_at.map(|issued_at| issued_at.0),
+            expires_at: metadata.expires_at.map(|expires_at| expires_at.0),
+            reference: metadata.reference,
+            reference_hash: metadata.reference_hash,
+        }
+    }
+}
+
+fn sbt_token_id(token_id: &str) -> SbtTokenId {
+    token_id.parse().unwrap_or_else(|_| env::panic_str("Invalid token id"))
+}
+
+fn is_expired(token: &Token) -> bool {
+    match token.metadata.expires_at {
+        Some(expires_at) => expires_at.0 <= env::block_timestamp(),
+        None => false
+    }
+}
+
+/// The NEP-393 soulbound token registry, over the badges. The issuer of NEP-393 is the
+/// community: the registry methods act on the badges of the community that calls them, and
+/// minting or recovering them requires the community to have added itself as an issuer.
+/// Badges minted by other issuers have class `0`.
+#[ext_contract(ext_sbt_registry)]
+pub trait SoulboundTokenRegistry {
+    /// Issues badges of the caller's community. Requires a deposit to cover the storage.
+    fn sbt_mint(&mut self, token_spec: Vec<(AccountId, Vec<SbtTokenMetadata>)>) -> Vec<SbtTokenId>;
+
+    /// Moves all the badges of the caller's community from `owner` to `recipient`.
+    /// Requires a deposit to cover the added storage.
+    fn sbt_recover(&mut self, owner: AccountId, recipient: AccountId);
+
+    /// Removes the badges if `burn`, or else makes them expire now.
+    /// Requires 1 yoctoNEAR attached.
+    fn sbt_revoke(&mut self, tokens: Vec<SbtTokenId>, burn: bool);
+
+    fn sbt(&self, issuer: AccountId, token: SbtTokenId) -> Option<SbtToken>;
+
+    fn sbt_supply(&self, issuer: AccountId) -> u64;
+
+    /// Returns up to `limit` badges of `account`, grouped by community and ordered by community and class.
+    fn sbt_tokens_by_owner(
+        &self,
+        account: AccountId,
+        issuer: Option<AccountId>,
+        from_class: Option<u64>,
+        limit: Option<u32>,
+        with_expired: Option<bool>,
+    ) -> Vec<(AccountId, Vec<SbtOwnedToken>)>;
+}
+
+impl SoulboundTokenRegistry for SoulboundToken {
+    fn sbt_mint(&mut self, token_spec: Vec<(AccountId, Vec<SbtTokenMetadata>)>) -> Vec<SbtTokenId> {
+        let initial_storage_usage = env::storage_usage();
+        let issuer = env::predecessor_account_id();
+        require!(self.is_issuer(&issuer, &issuer), "The community has to add itself as an issuer first");
+        let mut minted = vec![];
+        for (owner, metadata) in token_spec {
+            let token_ids: Vec<SbtTokenId> = metadata
+                .into_iter()
+                .map(|metadata| sbt_token_id(&self.internal_insert_token(&issuer, &owner, metadata.into()).token_id))
+                .collect();
+            minted.push((owner, token_ids));
+        }
+        let tokens: Vec<(&AccountId, &[u64])> = minted.iter().map(|(owner, token_ids)| (owner, token_ids.as_slice())).collect();
+        SbtMint { issuer: &issuer, tokens: &tokens }.emit();
+        settle_storage_deposit(initial_storage_usage);
+        minted.into_iter().flat_map(|(_, token_ids)| token_ids).collect()
+    }
+
+    fn sbt_recover(&mut self, owner: AccountId, recipient: AccountId) {
+        let initial_storage_usage = env::storage_usage();
+        let issuer = env::predecessor_account_id();
+        require!(self.is_issuer(&issuer, &issuer), "The community has to add itself as an issuer first");
+        let token_ids = self.internal_recover(&issuer, &owner, &recipient);
+        let tokens: Vec<SbtTokenId> = token_ids.iter().map(|token_id| sbt_token_id(token_id)).collect();
+        SbtRecover { issuer: &issuer, old_owner: &owner, new_owner: &recipient, tokens: &tokens }.emit();
+        settle_storage_deposit(initial_storage_usage);
+    }
+
+    fn sbt_revoke(&mut self, tokens: Vec<SbtTokenId>, burn: bool) {
+        assert_one_yocto();
+        let issuer = env::predecessor_account_id();
+        for token in &tokens {
+            let token_id = token.to_string();
+            let mut token = self.internal_unwrap_token(&token_id);
+            require!(token.contract_id == issuer, "Only the issuer can revoke");
+            if burn {
+                self.internal_remove_token(&token_id);
+            } else {
+                token.metadata.expires_at = Some(env::block_timestamp().into());
+                self.tokens.insert(&token_id, &token);
+            }
+        }
+        if burn {
+            SbtBurn { issuer: &issuer, tokens: &tokens }.emit();
+        } else {
+            SbtRevoke { issuer: &issuer, tokens: &tokens }.emit();
+        }
+    }
+
+    fn sbt(&self, issuer: AccountId, token: SbtTokenId) -> Option<SbtToken> {
+        self.tokens
+            .get(&token.to_string())
+            .filter(|token| token.contract_id == issuer)
+            .map(|token| SbtToken { token: sbt_token_id(&token.token_id), owner: token.owner_id, metadata: token.metadata.into() })
+    }
+
+    fn sbt_supply(&self, issuer: AccountId) -> u64 {
+        self.tokens_per_community.get(&issuer).map_or(0, |token_ids| token_ids.len())
+    }
+
+    fn sbt_tokens_by_owner(
+        &self,
+        account: AccountId,
+        issuer: Option<AccountId>,
+        from_class: Option<u64>,
+        limit: Option<u32>,
+        with_expired: Option<bool>,
+    ) -> Vec<(AccountId, Vec<SbtOwnedToken>)> {
+        let token_ids = match self.tokens_per_owner.get(&account) {
+            Some(token_ids) => token_ids,
+            None => return vec![]
+        };
+        let with_expired = with_expired.unwrap_or(false);
+        let mut by_issuer: BTreeMap<AccountId, Vec<SbtOwnedToken>> = BTreeMap::new();
+        for token in token_ids.iter().filter_map(|token_id| self.tokens.get(&token_id)) {
+            if matches!(&issuer, Some(issuer) if issuer != &token.contract_id) || (!with_expired && is_expired(&token)) {
+                continue;
+            }
+            let owned_token = SbtOwnedToken { token: sbt_token_id(&token.token_id), metadata: token.metadata.into() };
+            if owned_token.metadata.class < from_class.unwrap_or(0) {
+                continue;
+            }
+            by_issuer.entry(token.contract_id).or_default().push(owned_token);
+        }
+        let mut limit = limit.map_or(usize::MAX, |limit| limit as usize);
+        let mut tokens_by_owner = vec![];
+        for (issuer, mut tokens) in by_issuer {
+            if limit == 0 {
+                break;
+            }
+            tokens.sort_by_key(|token| (token.metadata.class, token.token));
+            tokens.truncate(limit);
+            limit -= tokens.len();
+            tokens_by_owner.push((issuer, tokens));
+        }
+        tokens_by_owner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::non_fungible_token::core::SoulboundTokenIssuers;
+    use crate::test_utils::set_caller;
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::ONE_NEAR;
+
+    fn metadata(class: ClassId) -> SbtTokenMetadata {
+        SbtTokenMetadata { class, issued_at: None, expires_at: None, reference: None, reference_hash: None }
+    }
+
+    #[test]
+    fn mint_recover_revoke() {
+        set_caller(accounts(0), ONE_NEAR);
+        let mut badges = SoulboundToken::new(b"sbt".to_vec());
+        badges.nft_add_issuer(accounts(0));
+        set_caller(accounts(0), ONE_NEAR);
+        assert_eq!(badges.sbt_mint(vec![(accounts(1), vec![metadata(2), metadata(1)])]), vec![0, 1]);
+        assert!(!get_logs().iter().any(|log| log.contains("nep171")));
+        assert!(get_logs()[0].contains(r#""event":"mint","data":{"issuer":"alice","tokens":[["bob",[0,1]]]}"#));
+        assert_eq!(
+            badges.sbt_tokens_by_owner(accounts(1), None, None, None, None),
+            vec![(accounts(0), vec![SbtOwnedToken { token: 1, metadata: metadata(1) }, SbtOwnedToken { token: 0, metadata: metadata(2) }])]
+        );
+
+        badges.sbt_recover(accounts(1), accounts(2));
+        assert!(badges.sbt_tokens_by_owner(accounts(1), None, None, None, None).is_empty());
+        assert_eq!(badges.sbt(accounts(0), 0).unwrap().owner, accounts(2));
+
+        set_caller(accounts(0), 1);
+        badges.sbt_revoke(vec![0], false);
+        assert_eq!(badges.sbt_tokens_by_owner(accounts(2), None, None, None, None)[0].1.len(), 1);
+        assert_eq!(badges.sbt_tokens_by_owner(accounts(2), None, None, None, Some(true))[0].1.len(), 2);
+        set_caller(accounts(0), 1);
+        badges.sbt_revoke(vec![1], true);
+        assert_eq!(get_logs().len(), 1);
+        assert!(get_logs()[0].contains(r#""event":"burn","data":{"issuer":"alice","tokens":[1]}"#));
+        assert_eq!(badges.sbt_supply(accounts(0)), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The community has to add itself as an issuer first")]
+    fn mint_without_issuer_entry() {
+        set_caller(accounts(0), ONE_NEAR);
+        let mut badges = SoulboundToken::new(b"sbt".to_vec());
+        badges.sbt_mint(vec![(accounts(1), vec![metadata(1)])]);
+    }
+}
@@ -0,0 +1,293 @@
+//! The events of the `ntt` standard, listed once for the contract and for the replay.
+//!
+//! [`ntt_events!`] hands every event, with its documentation, its `serde` attributes and the
+//! kind of each field, to a macro that generates the types. `fungible_token::events` generates
+//! the borrowed structs the contract logs, and `replay::events` the owned structs the logs are
+//! decoded into, so both sides always agree on the names and the fields.
+//!
+//! The field kinds are `account`, `option_account`, `accounts`, `amount`, `id`, `str`,
+//! `option_str`, `bool`, `u32`, `pause` and `admin_action`. Each side maps them to its own types.
+
+/// Calls `$generator!` with every event of the standard, in the form
+/// `$(#[$meta])* Name { $(#[$field_meta])* field: kind, ... }`.
+macro_rules! ntt_events {
+    ($generator:ident) => {
+        $generator! {
+            /// Data to log for an FT mint event.
+            FtMint {
+                owner_id: account,
+                contract_id: account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                memo: option_str,
+            }
+
+            /// Data to log for an FT deposit event.
+            FtDeposit {
+                owner_id: account,
+                contract_id: account,
+                deposit_contract_id: account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                memo: option_str,
+            }
+
+            /// Data to log for an FT withdraw event.
+            FtWithdraw {
+                owner_id: account,
+                contract_id: account,
+                deposit_contract_id: account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                memo: option_str,
+            }
+
+            /// Data to log for an FT burn event.
+            FtBurn {
+                owner_id: account,
+                contract_id: account,
+                /// Set when the burn went through `ft_burn_call`.
+                #[serde(skip_serializing_if = "Option::is_none")]
+                receiver_id: option_account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                memo: option_str,
+            }
+
+            /// Data to log for the part of a deposit the receiver did not use and returned to the owner.
+            FtDepositRefund {
+                owner_id: account,
+                contract_id: account,
+                deposit_contract_id: account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                memo: option_str,
+            }
+
+            /// Data to log for the part of a withdrawal the receiver did not accept and locked again.
+            FtWithdrawRefund {
+                owner_id: account,
+                contract_id: account,
+                deposit_contract_id: account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                memo: option_str,
+            }
+
+            /// Data to log for the part of a burn the receiver did not use and returned to the owner.
+            FtBurnRefund {
+                owner_id: account,
+                contract_id: account,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                receiver_id: option_account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                memo: option_str,
+            }
+
+            /// Data to log when community `contract_id` burns from the available balance of `owner_id`.
+            FtClawback {
+                owner_id: account,
+                contract_id: account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                reason: option_str,
+            }
+
+            /// Data to log when community `contract_id` burns from the deposit of `owner_id`.
+            FtSlash {
+                owner_id: account,
+                contract_id: account,
+                deposit_contract_id: account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                reason: option_str,
+            }
+
+            /// Data to log when a mint waits for `owner_id` to accept it.
+            FtMintPending {
+                owner_id: account,
+                contract_id: account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                memo: option_str,
+            }
+
+            /// Data to log when `owner_id` rejects a pending mint.
+            FtMintRejected {
+                owner_id: account,
+                contract_id: account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                memo: option_str,
+            }
+
+            /// Data to log when an account is registered with the token.
+            AccountRegistered {
+                account_id: account,
+            }
+
+            /// Data to log when an account is unregistered from the token.
+            AccountUnregistered {
+                account_id: account,
+            }
+
+            /// Data to log when `account_id` locks `amount` yoctoNEAR for storage.
+            StorageChanged {
+                account_id: account,
+                amount: amount,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                memo: option_str,
+            }
+
+            /// Data to log when the first tokens of community `contract_id` are minted.
+            CommunityCreated {
+                contract_id: account,
+            }
+
+            /// Data to log when `sender_id` grants `role` to `account_id`, in community `contract_id` if set.
+            RoleGranted {
+                role: str,
+                account_id: account,
+                sender_id: account,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                contract_id: option_account,
+            }
+
+            /// Data to log when `sender_id` revokes `role` from `account_id`, in community `contract_id` if set.
+            RoleRevoked {
+                role: str,
+                account_id: account,
+                sender_id: account,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                contract_id: option_account,
+            }
+
+            /// Data to log when `sender_id` updates the token metadata, or that of community `contract_id`.
+            MetadataUpdated {
+                sender_id: account,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                contract_id: option_account,
+            }
+
+            /// Data to log when `owner_id` burns its tokens of community `contract_id` and drops the entry.
+            CommunityRenounced {
+                owner_id: account,
+                contract_id: account,
+                /// Whether further mints of the community are refused.
+                blocked: bool,
+            }
+
+            /// Data to log when `account_id` changes which mints it accepts.
+            ConsentPolicyUpdated {
+                account_id: account,
+            }
+
+            /// Data to log when `sender_id` asks to recover `account_id` onto `new_account_id`.
+            RecoveryRequested {
+                account_id: account,
+                new_account_id: account,
+                sender_id: account,
+            }
+
+            /// Data to log when `account_id` replaces its recovery guardians. An empty list removes them.
+            RecoveryGuardiansUpdated {
+                account_id: account,
+                guardians: accounts,
+                threshold: u32,
+            }
+
+            /// Data to log when `sender_id` approves the pending recovery of `account_id`.
+            RecoveryApproved {
+                account_id: account,
+                sender_id: account,
+            }
+
+            /// Data to log when `sender_id` cancels the pending recovery of `account_id`.
+            RecoveryCancelled {
+                account_id: account,
+                sender_id: account,
+            }
+
+            /// Data to log when a recovery moves every balance of `account_id` onto `new_account_id`.
+            AccountRecovered {
+                account_id: account,
+                new_account_id: account,
+            }
+
+            /// Data to log when `account_id` proposes to merge into `new_account_id`.
+            MergeProposed {
+                account_id: account,
+                new_account_id: account,
+            }
+
+            /// Data to log when `account_id` withdraws its proposal to merge into `new_account_id`.
+            MergeCancelled {
+                account_id: account,
+                new_account_id: account,
+            }
+
+            /// Data to log when a merge adds every balance of `account_id` to `new_account_id`.
+            AccountMerged {
+                account_id: account,
+                new_account_id: account,
+            }
+
+            /// Data to log when `sender_id` pauses operations globally, or for community `contract_id` if set.
+            Paused {
+                sender_id: account,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                contract_id: option_account,
+                pause: pause,
+            }
+
+            /// Data to log when `sender_id` lifts the global pause, the pause of `contract_id` or the freeze of `account_id`.
+            Unpaused {
+                sender_id: account,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                contract_id: option_account,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                account_id: option_account,
+            }
+
+            /// Data to log when `sender_id` freezes operations of `account_id`.
+            Frozen {
+                sender_id: account,
+                account_id: account,
+                pause: pause,
+            }
+
+            /// Data to log when `proposer_id` queues the admin action `id`.
+            AdminActionProposed {
+                id: id,
+                proposer_id: account,
+                action: admin_action,
+            }
+
+            /// Data to log when `sender_id` approves the admin action `id`.
+            AdminActionApproved {
+                id: id,
+                sender_id: account,
+            }
+
+            /// Data to log when `sender_id` cancels the admin action `id`.
+            AdminActionCancelled {
+                id: id,
+                sender_id: account,
+            }
+
+            /// Data to log when `sender_id` votes to cancel the admin action `id`.
+            AdminCancelApproved {
+                id: id,
+                sender_id: account,
+            }
+
+            /// Data to log when `sender_id` executes the admin action `id`, after the changes it made
+            /// were logged.
+            AdminActionExecuted {
+                id: id,
+                sender_id: account,
+            }
+        }
+    };
+}
@@ -0,0 +1,285 @@
+//! Owned counterparts of the events in `fungible_token::events`,
+//! for decoding the `EVENT_JSON:` logs off-chain.
+//!
+//! Both sides are generated from the same list of `ntt_events!`. Both the `ntt` `2.0.0` logs and
+//! the `nep141` `1.0.0` logs of the `nep141-events` feature are decoded into [`EventKind`]; the
+//! fields that `1.0.0` packed into `memo` are unpacked again. Account ids are plain strings and
+//! amounts are [`U128`], so none of this needs the NEAR runtime.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+
+/// Implements a decimal string format for a number newtype, like `U128` and `U64` in the contract.
+macro_rules! impl_dec_format {
+    ($name:ident, $num:ty) => {
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0.to_string())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer)?.parse().map(Self).map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+/// An amount, logged as a decimal string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct U128(pub u128);
+
+impl_dec_format!(U128, u128);
+
+/// An admin action id, logged as a decimal string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct U64(pub u64);
+
+impl_dec_format!(U64, u64);
+
+/// The operations a pause or a freeze stops, like `fungible_token::pause::Pause`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pause {
+    pub mint: bool,
+    pub burn: bool,
+    pub deposit: bool,
+    pub withdraw: bool,
+}
+
+/// The owned type the logs decode into for each field kind of `ntt_events!`. The admin action
+/// is kept as JSON, as it only matters to the contract.
+macro_rules! replay_field {
+    (account) => { String };
+    (option_account) => { Option<String> };
+    (accounts) => { Vec<String> };
+    (amount) => { U128 };
+    (id) => { U64 };
+    (str) => { String };
+    (option_str) => { Option<String> };
+    (bool) => { bool };
+    (u32) => { u32 };
+    (pause) => { Pause };
+    (admin_action) => { Value };
+}
+
+/// Generates an owned struct per event, decoded through the [`EventKind`] variant of the same name.
+macro_rules! replay_event_structs {
+    ($(
+        $(#[$meta:meta])*
+        $event:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field:ident: $kind:ident,
+            )*
+        }
+    )*) => {
+        $(
+            $(#[$meta])*
+            #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+            pub struct $event {
+                $(
+                    $(#[$field_meta])*
+                    pub $field: replay_field!($kind),
+                )*
+            }
+        )*
+
+        #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+        #[serde(tag = "event", content = "data")]
+        #[serde(rename_all = "snake_case")]
+        #[allow(clippy::enum_variant_names)]
+        pub enum EventKind {
+            $($event(Vec<$event>),)*
+        }
+    };
+}
+
+ntt_events!(replay_event_structs);
+
+/// A decoded `EVENT_JSON:` log of this token.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EventLog {
+    pub standard: String,
+    pub version: String,
+    #[serde(flatten)]
+    pub event_kind: EventKind,
+}
+
+impl EventLog {
+    /// Decodes a log line. Returns `None` for plain logs and for events of other standards.
+    pub fn from_log(log: &str) -> Result<Option<Self>, serde_json::Error> {
+        let json = match log.strip_prefix(EVENT_JSON_PREFIX) {
+            Some(json) => json,
+            None => return Ok(None),
+        };
+        let mut value: Value = serde_json::from_str(json)?;
+        match value.get("standard").and_then(Value::as_str) {
+            Some("ntt") => {}
+            Some("nep141") => unpack_memos(&mut value),
+            _ => return Ok(None),
+        }
+        serde_json::from_value(value).map(Some)
+    }
+}
+
+/// Moves the fields that `nep141` `1.0.0` packed into `memo` back onto the event data.
+/// A `memo` that is not a JSON object is left as it is.
+fn unpack_memos(value: &mut Value) {
+    let data = match value.get_mut("data").and_then(Value::as_array_mut) {
+        Some(data) => data,
+        None => return,
+    };
+    for data in data.iter_mut().filter_map(Value::as_object_mut) {
+        let fields = match data.get("memo").and_then(Value::as_str).map(serde_json::from_str::<Map<String, Value>>) {
+            Some(Ok(fields)) => fields,
+            _ => continue,
+        };
+        data.remove("memo");
+        data.extend(fields);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_ntt() {
+        let log = r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_burn","data":[{"owner_id":"alice","contract_id":"community","receiver_id":"receiver","amount":"200","memo":"has memo"},{"owner_id":"bob","contract_id":"community","amount":"100"}]}"#;
+        assert_eq!(
+            EventLog::from_log(log).unwrap(),
+            Some(EventLog {
+                standard: "ntt".to_string(),
+                version: "2.0.0".to_string(),
+                event_kind: EventKind::FtBurn(vec![
+                    FtBurn {
+                        owner_id: "alice".to_string(),
+                        contract_id: "community".to_string(),
+                        receiver_id: Some("receiver".to_string()),
+                        amount: U128(200),
+                        memo: Some("has memo".to_string()),
+                    },
+                    FtBurn {
+                        owner_id: "bob".to_string(),
+                        contract_id: "community".to_string(),
+                        receiver_id: None,
+                        amount: U128(100),
+                        memo: None,
+                    },
+                ]),
+            })
+        );
+    }
+
+    #[test]
+    fn decode_nep141() {
+        let log = r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_deposit","data":[{"owner_id":"bob","amount":"100","memo":"{\"contract_id\":\"community\",\"deposit_contract_id\":\"receiver\",\"memo\":\"has memo\"}"}]}"#;
+        assert_eq!(
+            EventLog::from_log(log).unwrap().unwrap().event_kind,
+            EventKind::FtDeposit(vec![FtDeposit {
+                owner_id: "bob".to_string(),
+                contract_id: "community".to_string(),
+                deposit_contract_id: "receiver".to_string(),
+                amount: U128(100),
+                memo: Some("has memo".to_string()),
+            }])
+        );
+    }
+
+    #[test]
+    fn skip_other_logs() {
+        assert_eq!(EventLog::from_log("The account is already registered, refunding the deposit").unwrap(), None);
+        assert_eq!(
+            EventLog::from_log(r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[]}"#).unwrap(),
+            None
+        );
+        assert!(EventLog::from_log(r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_mint","data":[{"owner_id":"bob"}]}"#).is_err());
+    }
+
+    /// What the contract logs decodes back into the same events, in either shape it logs.
+    #[cfg(feature = "contract")]
+    #[test]
+    fn decode_contract_logs() {
+        use crate::access_control::timelock::AdminAction;
+        use crate::fungible_token::{events, pause};
+        use near_sdk::json_types::{U128 as NearU128, U64 as NearU64};
+        use near_sdk::{test_utils, AccountId};
+
+        let bob = AccountId::new_unchecked("bob".to_string());
+        let community = AccountId::new_unchecked("community".to_string());
+        let receiver = AccountId::new_unchecked("receiver".to_string());
+        let pause = pause::Pause { mint: true, burn: false, deposit: true, withdraw: false };
+        events::FtMint { owner_id: &bob, contract_id: &community, amount: &NearU128(100), memo: None }.emit();
+        events::FtBurn::emit_many(&[
+            events::FtBurn { owner_id: &bob, contract_id: &community, receiver_id: Some(&receiver), amount: &NearU128(20), memo: Some("has memo") },
+            events::FtBurn { owner_id: &bob, contract_id: &community, receiver_id: None, amount: &NearU128(u128::MAX), memo: None },
+        ]);
+        events::FtWithdrawRefund { owner_id: &bob, contract_id: &community, deposit_contract_id: &receiver, amount: &NearU128(10), memo: None }.emit();
+        events::FtSlash { owner_id: &bob, contract_id: &community, deposit_contract_id: &receiver, amount: &NearU128(5), reason: Some("sybil") }.emit();
+        events::RoleGranted { role: "minter", account_id: &bob, sender_id: &community, contract_id: Some(&community) }.emit();
+        events::RecoveryGuardiansUpdated { account_id: &bob, guardians: &[community.clone(), receiver.clone()], threshold: 2 }.emit();
+        events::CommunityRenounced { owner_id: &bob, contract_id: &community, blocked: true }.emit();
+        events::Paused { sender_id: &bob, contract_id: None, pause: &pause }.emit();
+        events::AdminActionProposed { id: &NearU64(7), proposer_id: &bob, action: &AdminAction::SetPause { pause } }.emit();
+
+        let event_kinds: Vec<EventKind> = test_utils::get_logs()
+            .iter()
+            .map(|log| EventLog::from_log(log).unwrap().unwrap().event_kind)
+            .collect();
+        assert_eq!(
+            event_kinds,
+            vec![
+                EventKind::FtMint(vec![FtMint { owner_id: "bob".to_string(), contract_id: "community".to_string(), amount: U128(100), memo: None }]),
+                EventKind::FtBurn(vec![
+                    FtBurn {
+                        owner_id: "bob".to_string(),
+                        contract_id: "community".to_string(),
+                        receiver_id: Some("receiver".to_string()),
+                        amount: U128(20),
+                        memo: Some("has memo".to_string()),
+                    },
+                    FtBurn { owner_id: "bob".to_string(), contract_id: "community".to_string(), receiver_id: None, amount: U128(u128::MAX), memo: None },
+                ]),
+                EventKind::FtWithdrawRefund(vec![FtWithdrawRefund {
+                    owner_id: "bob".to_string(),
+                    contract_id: "community".to_string(),
+                    deposit_contract_id: "receiver".to_string(),
+                    amount: U128(10),
+                    memo: None,
+                }]),
+                EventKind::FtSlash(vec![FtSlash {
+                    owner_id: "bob".to_string(),
+                    contract_id: "community".to_string(),
+                    deposit_contract_id: "receiver".to_string(),
+                    amount: U128(5),
+                    reason: Some("sybil".to_string()),
+                }]),
+                EventKind::RoleGranted(vec![RoleGranted {
+                    role: "minter".to_string(),
+                    account_id: "bob".to_string(),
+                    sender_id: "community".to_string(),
+                    contract_id: Some("community".to_string()),
+                }]),
+                EventKind::RecoveryGuardiansUpdated(vec![RecoveryGuardiansUpdated {
+                    account_id: "bob".to_string(),
+                    guardians: vec!["community".to_string(), "receiver".to_string()],
+                    threshold: 2,
+                }]),
+                EventKind::CommunityRenounced(vec![CommunityRenounced { owner_id: "bob".to_string(), contract_id: "community".to_string(), blocked: true }]),
+                EventKind::Paused(vec![Paused {
+                    sender_id: "bob".to_string(),
+                    contract_id: None,
+                    pause: Pause { mint: true, burn: false, deposit: true, withdraw: false },
+                }]),
+                EventKind::AdminActionProposed(vec![AdminActionProposed {
+                    id: U64(7),
+                    proposer_id: "bob".to_string(),
+                    action: serde_json::json!({ "set_pause": { "pause": { "mint": true, "burn": false, "deposit": true, "withdraw": false } } }),
+                }]),
+            ]
+        );
+    }
+}
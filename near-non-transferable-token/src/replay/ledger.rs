@@ -0,0 +1,418 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::replay::events::{EventKind, EventLog};
+
+/// The balance of an account in one community, as the contract keeps it: `total` is what the
+/// account has received, `available` what it can still deposit or burn, and `deposits` what is
+/// locked with each receiving contract.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Balance {
+    pub available: u128,
+    pub total: u128,
+    pub deposits: BTreeMap<String, u128>,
+}
+
+/// The supply of a community, like `ft_available_supply` and `ft_total_supply`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Supply {
+    pub available: u128,
+    pub total: u128,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The log line is not a valid event.
+    Decode(serde_json::Error),
+    /// An event took more than the balance had, so events are missing or out of order.
+    NotEnoughBalance { event: &'static str, owner_id: String, contract_id: String, amount: u128 },
+    /// An event gave more than the balance can hold.
+    BalanceOverflow { event: &'static str, owner_id: String, contract_id: String, amount: u128 },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(error) => write!(f, "Invalid event: {}", error),
+            Self::NotEnoughBalance { event, owner_id, contract_id, amount } => {
+                write!(f, "Not enough balance for {} of {} by {} in {}", event, amount, owner_id, contract_id)
+            }
+            Self::BalanceOverflow { event, owner_id, contract_id, amount } => {
+                write!(f, "Balance overflow for {} of {} by {} in {}", event, amount, owner_id, contract_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Decode(error)
+    }
+}
+
+/// Balances rebuilt from the events of the token.
+///
+/// Events are applied as they come. A deposit, withdrawal or burn with a call is logged in full
+/// when the call is made and its refund event gives back what the receiver returned. Pending
+/// mints do not change the balances until they are accepted as `ft_mint`.
+///
+/// Only tokens minted through `FungibleToken::internal_mint` are replayed, since
+/// `FungibleToken::internal_deposit` credits an account without logging `ft_mint`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ledger {
+    /// owner_id -> contract_id -> balance
+    pub accounts: BTreeMap<String, BTreeMap<String, Balance>>,
+    /// contract_id -> supply
+    pub supply: BTreeMap<String, Supply>,
+    pub registered: BTreeSet<String>,
+}
+
+impl Ledger {
+    /// Replays the log lines in order, skipping the lines that are not events of this token.
+    pub fn replay<I, S>(logs: I) -> Result<Self, ReplayError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut ledger = Self::default();
+        for log in logs {
+            ledger.apply_log(log.as_ref())?;
+        }
+        Ok(ledger)
+    }
+
+    pub fn apply_log(&mut self, log: &str) -> Result<(), ReplayError> {
+        match EventLog::from_log(log)? {
+            Some(event_log) => self.apply(&event_log.event_kind),
+            None => Ok(()),
+        }
+    }
+
+    pub fn apply(&mut self, event_kind: &EventKind) -> Result<(), ReplayError> {
+        match event_kind {
+            EventKind::FtMint(events) => {
+                for e in events {
+                    let balance = self.balance_mut(&e.owner_id, &e.contract_id);
+                    balance.available = add(balance.available, e.amount.0, "ft_mint", &e.owner_id, &e.contract_id)?;
+                    balance.total = add(balance.total, e.amount.0, "ft_mint", &e.owner_id, &e.contract_id)?;
+                    let supply = self.supply.entry(e.contract_id.clone()).or_default();
+                    supply.available = add(supply.available, e.amount.0, "ft_mint", &e.owner_id, &e.contract_id)?;
+                    supply.total = add(supply.total, e.amount.0, "ft_mint", &e.owner_id, &e.contract_id)?;
+                }
+            }
+            EventKind::FtDeposit(events) => {
+                for e in events {
+                    self.lock_deposit(&e.owner_id, &e.contract_id, &e.deposit_contract_id, e.amount.0, "ft_deposit")?;
+                }
+            }
+            EventKind::FtWithdrawRefund(events) => {
+                for e in events {
+                    self.lock_deposit(&e.owner_id, &e.contract_id, &e.deposit_contract_id, e.amount.0, "ft_withdraw_refund")?;
+                }
+            }
+            EventKind::FtWithdraw(events) => {
+                for e in events {
+                    self.unlock_deposit(&e.owner_id, &e.contract_id, &e.deposit_contract_id, e.amount.0, "ft_withdraw")?;
+                }
+            }
+            EventKind::FtDepositRefund(events) => {
+                for e in events {
+                    self.unlock_deposit(&e.owner_id, &e.contract_id, &e.deposit_contract_id, e.amount.0, "ft_deposit_refund")?;
+                }
+            }
+            EventKind::FtBurn(events) => {
+                for e in events {
+                    let balance = self.balance_mut(&e.owner_id, &e.contract_id);
+                    balance.available = sub(balance.available, e.amount.0, "ft_burn", &e.owner_id, &e.contract_id)?;
+                    let supply = self.supply.entry(e.contract_id.clone()).or_default();
+                    supply.available = sub(supply.available, e.amount.0, "ft_burn", &e.owner_id, &e.contract_id)?;
+                }
+            }
+            EventKind::FtBurnRefund(events) => {
+                for e in events {
+                    let balance = self.balance_mut(&e.owner_id, &e.contract_id);
+                    let available = add(balance.available, e.amount.0, "ft_burn_refund", &e.owner_id, &e.contract_id)?;
+                    if available > balance.total {
+                        return Err(ReplayError::BalanceOverflow {
+                            event: "ft_burn_refund",
+                            owner_id: e.owner_id.clone(),
+                            contract_id: e.contract_id.clone(),
+                            amount: e.amount.0,
+                        });
+                    }
+                    balance.available = available;
+                    let supply = self.supply.entry(e.contract_id.clone()).or_default();
+                    supply.available = add(supply.available, e.amount.0, "ft_burn_refund", &e.owner_id, &e.contract_id)?;
+                }
+            }
+            EventKind::FtClawback(events) => {
+                for e in events {
+                    let balance = self.balance_mut(&e.owner_id, &e.contract_id);
+                    balance.available = sub(balance.available, e.amount.0, "ft_clawback", &e.owner_id, &e.contract_id)?;
+                    let supply = self.supply.entry(e.contract_id.clone()).or_default();
+                    supply.available = sub(supply.available, e.amount.0, "ft_clawback", &e.owner_id, &e.contract_id)?;
+                }
+            }
+            EventKind::FtSlash(events) => {
+                for e in events {
+                    let balance = self.balance_mut(&e.owner_id, &e.contract_id);
+                    let deposit = balance.deposits.get(&e.deposit_contract_id).copied().unwrap_or(0);
+                    match sub(deposit, e.amount.0, "ft_slash", &e.owner_id, &e.contract_id)? {
+                        0 => balance.deposits.remove(&e.deposit_contract_id),
+                        deposit => balance.deposits.insert(e.deposit_contract_id.clone(), deposit),
+                    };
+                    let supply = self.supply.entry(e.contract_id.clone()).or_default();
+                    supply.available = sub(supply.available, e.amount.0, "ft_slash", &e.owner_id, &e.contract_id)?;
+                }
+            }
+            EventKind::AccountRegistered(events) => {
+                self.registered.extend(events.iter().map(|e| e.account_id.clone()));
+            }
+            EventKind::AccountUnregistered(events) => {
+                for e in events {
+                    self.registered.remove(&e.account_id);
+                }
+            }
+            EventKind::CommunityCreated(events) => {
+                for e in events {
+                    self.supply.entry(e.contract_id.clone()).or_default();
+                }
+            }
+            EventKind::CommunityRenounced(events) => {
+                for e in events {
+                    let removed = self.accounts.get_mut(&e.owner_id).and_then(|balances| balances.remove(&e.contract_id));
+                    if let (Some(balance), Some(supply)) = (removed, self.supply.get_mut(&e.contract_id)) {
+                        supply.total = supply.total.saturating_sub(balance.total);
+                    }
+                }
+            }
+            EventKind::AccountRecovered(events) => {
+                for e in events {
+                    self.move_account(&e.account_id, &e.new_account_id, "account_recovered")?;
+                }
+            }
+            EventKind::AccountMerged(events) => {
+                for e in events {
+                    self.move_account(&e.account_id, &e.new_account_id, "account_merged")?;
+                }
+            }
+            EventKind::FtMintPending(_)
+            | EventKind::FtMintRejected(_)
+            | EventKind::StorageChanged(_)
+            | EventKind::RoleGranted(_)
+            | EventKind::RoleRevoked(_)
+            | EventKind::MetadataUpdated(_)
+            | EventKind::ConsentPolicyUpdated(_)
+            | EventKind::Paused(_)
+            | EventKind::Unpaused(_)
+            | EventKind::Frozen(_)
+            | EventKind::RecoveryGuardiansUpdated(_)
+            | EventKind::RecoveryRequested(_)
+            | EventKind::RecoveryApproved(_)
+            | EventKind::RecoveryCancelled(_)
+            | EventKind::MergeProposed(_)
+            | EventKind::MergeCancelled(_)
+            | EventKind::AdminActionProposed(_)
+            | EventKind::AdminActionApproved(_)
+            | EventKind::AdminActionCancelled(_)
+            | EventKind::AdminCancelApproved(_)
+            | EventKind::AdminActionExecuted(_) => {}
+        }
+        Ok(())
+    }
+
+    pub fn balance(&self, owner_id: &str, contract_id: &str) -> Option<&Balance> {
+        self.accounts.get(owner_id).and_then(|balances| balances.get(contract_id))
+    }
+
+    /// Moves `amount` from the available balance to the deposit with `deposit_contract_id`.
+    fn lock_deposit(&mut self, owner_id: &str, contract_id: &str, deposit_contract_id: &str, amount: u128, event: &'static str) -> Result<(), ReplayError> {
+        let balance = self.balance_mut(owner_id, contract_id);
+        balance.available = sub(balance.available, amount, event, owner_id, contract_id)?;
+        let deposit = balance.deposits.entry(deposit_contract_id.to_string()).or_default();
+        *deposit = add(*deposit, amount, event, owner_id, contract_id)?;
+        Ok(())
+    }
+
+    /// Moves `amount` from the deposit with `deposit_contract_id` back to the available balance.
+    fn unlock_deposit(&mut self, owner_id: &str, contract_id: &str, deposit_contract_id: &str, amount: u128, event: &'static str) -> Result<(), ReplayError> {
+        let balance = self.balance_mut(owner_id, contract_id);
+        let deposit = balance.deposits.get(deposit_contract_id).copied().unwrap_or(0);
+        match sub(deposit, amount, event, owner_id, contract_id)? {
+            0 => balance.deposits.remove(deposit_contract_id),
+            deposit => balance.deposits.insert(deposit_contract_id.to_string(), deposit),
+        };
+        let available = add(balance.available, amount, event, owner_id, contract_id)?;
+        if available > balance.total {
+            return Err(ReplayError::BalanceOverflow {
+                event,
+                owner_id: owner_id.to_string(),
+                contract_id: contract_id.to_string(),
+                amount,
+            });
+        }
+        balance.available = available;
+        Ok(())
+    }
+
+    /// Adds every balance and deposit of `owner_id` to `new_owner_id`, like the contract does
+    /// when it moves an account. The registration changes come as their own events.
+    fn move_account(&mut self, owner_id: &str, new_owner_id: &str, event: &'static str) -> Result<(), ReplayError> {
+        let balances = self.accounts.remove(owner_id).unwrap_or_default();
+        for (contract_id, moved) in balances {
+            let balance = self.balance_mut(new_owner_id, &contract_id);
+            balance.available = add(balance.available, moved.available, event, new_owner_id, &contract_id)?;
+            balance.total = add(balance.total, moved.total, event, new_owner_id, &contract_id)?;
+            for (deposit_contract_id, amount) in moved.deposits {
+                let deposit = balance.deposits.entry(deposit_contract_id).or_default();
+                *deposit = add(*deposit, amount, event, new_owner_id, &contract_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn balance_mut(&mut self, owner_id: &str, contract_id: &str) -> &mut Balance {
+        self.accounts
+            .entry(owner_id.to_string())
+            .or_default()
+            .entry(contract_id.to_string())
+            .or_default()
+    }
+}
+
+fn add(balance: u128, amount: u128, event: &'static str, owner_id: &str, contract_id: &str) -> Result<u128, ReplayError> {
+    balance.checked_add(amount).ok_or_else(|| ReplayError::BalanceOverflow {
+        event,
+        owner_id: owner_id.to_string(),
+        contract_id: contract_id.to_string(),
+        amount,
+    })
+}
+
+fn sub(balance: u128, amount: u128, event: &'static str, owner_id: &str, contract_id: &str) -> Result<u128, ReplayError> {
+    balance.checked_sub(amount).ok_or_else(|| ReplayError::NotEnoughBalance {
+        event,
+        owner_id: owner_id.to_string(),
+        contract_id: contract_id.to_string(),
+        amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(event: &str, data: &str) -> String {
+        format!(r#"EVENT_JSON:{{"standard":"ntt","version":"2.0.0","event":"{}","data":[{}]}}"#, event, data)
+    }
+
+    #[test]
+    fn replay_deposit_and_burn_call() {
+        let logs = vec![
+            log("account_registered", r#"{"account_id":"bob"}"#),
+            log("community_created", r#"{"contract_id":"community"}"#),
+            log("ft_mint", r#"{"owner_id":"bob","contract_id":"community","amount":"1000"}"#),
+            "a plain log".to_string(),
+            log("ft_deposit", r#"{"owner_id":"bob","contract_id":"community","deposit_contract_id":"danny","amount":"100"}"#),
+            log("ft_deposit_refund", r#"{"owner_id":"bob","contract_id":"community","deposit_contract_id":"danny","amount":"20"}"#),
+            log("ft_withdraw", r#"{"owner_id":"bob","contract_id":"community","deposit_contract_id":"danny","amount":"40"}"#),
+            log("ft_withdraw_refund", r#"{"owner_id":"bob","contract_id":"community","deposit_contract_id":"danny","amount":"10"}"#),
+            log("ft_burn", r#"{"owner_id":"bob","contract_id":"community","receiver_id":"danny","amount":"100"}"#),
+            log("ft_burn_refund", r#"{"owner_id":"bob","contract_id":"community","receiver_id":"danny","amount":"40"}"#),
+        ];
+        let ledger = Ledger::replay(&logs).unwrap();
+        let balance = ledger.balance("bob", "community").unwrap();
+        assert_eq!(balance.available, 890);
+        assert_eq!(balance.total, 1000);
+        assert_eq!(balance.deposits.get("danny"), Some(&50));
+        assert_eq!(ledger.supply["community"], Supply { available: 940, total: 1000 });
+        assert!(ledger.registered.contains("bob"));
+    }
+
+    #[test]
+    fn replay_deposit_released_in_flight() {
+        // The receiver releases most of the deposit before `ft_deposit_call` resolves, so the
+        // refund can only return what is left.
+        let logs = vec![
+            log("ft_mint", r#"{"owner_id":"bob","contract_id":"community","amount":"1000"}"#),
+            log("ft_deposit", r#"{"owner_id":"bob","contract_id":"community","deposit_contract_id":"danny","amount":"100"}"#),
+            log("ft_withdraw", r#"{"owner_id":"bob","contract_id":"community","deposit_contract_id":"danny","amount":"80"}"#),
+            log("ft_deposit_refund", r#"{"owner_id":"bob","contract_id":"community","deposit_contract_id":"danny","amount":"20"}"#),
+        ];
+        let ledger = Ledger::replay(&logs).unwrap();
+        let balance = ledger.balance("bob", "community").unwrap();
+        assert_eq!((balance.available, balance.total), (1000, 1000));
+        assert!(balance.deposits.is_empty());
+    }
+
+    #[test]
+    fn replay_account_recovered() {
+        let logs = vec![
+            log("account_registered", r#"{"account_id":"bob"}"#),
+            log("ft_mint", r#"{"owner_id":"bob","contract_id":"community","amount":"100"}"#),
+            log("ft_deposit", r#"{"owner_id":"bob","contract_id":"community","deposit_contract_id":"danny","amount":"40"}"#),
+            log("recovery_requested", r#"{"account_id":"bob","new_account_id":"fargo","sender_id":"community"}"#),
+            log("account_registered", r#"{"account_id":"fargo"}"#),
+            log("account_unregistered", r#"{"account_id":"bob"}"#),
+            log("account_recovered", r#"{"account_id":"bob","new_account_id":"fargo"}"#),
+        ];
+        let ledger = Ledger::replay(&logs).unwrap();
+        assert_eq!(ledger.balance("bob", "community"), None);
+        let balance = ledger.balance("fargo", "community").unwrap();
+        assert_eq!((balance.available, balance.total), (60, 100));
+        assert_eq!(balance.deposits.get("danny"), Some(&40));
+        assert_eq!(ledger.supply["community"], Supply { available: 100, total: 100 });
+        assert_eq!(ledger.registered.iter().collect::<Vec<_>>(), vec!["fargo"]);
+    }
+
+    #[test]
+    fn replay_clawback_and_slash() {
+        let logs = vec![
+            log("ft_mint", r#"{"owner_id":"bob","contract_id":"community","amount":"100"}"#),
+            log("ft_deposit", r#"{"owner_id":"bob","contract_id":"community","deposit_contract_id":"danny","amount":"40"}"#),
+            log("ft_clawback", r#"{"owner_id":"bob","contract_id":"community","amount":"60","reason":"abuse"}"#),
+            log("ft_slash", r#"{"owner_id":"bob","contract_id":"community","deposit_contract_id":"danny","amount":"40"}"#),
+        ];
+        let ledger = Ledger::replay(&logs).unwrap();
+        let balance = ledger.balance("bob", "community").unwrap();
+        assert_eq!((balance.available, balance.total), (0, 100));
+        assert!(balance.deposits.is_empty());
+        assert_eq!(ledger.supply["community"], Supply { available: 0, total: 100 });
+    }
+
+    #[test]
+    fn replay_community_renounced() {
+        let logs = vec![
+            log("ft_mint", r#"{"owner_id":"bob","contract_id":"community","amount":"100"}"#),
+            log("ft_burn", r#"{"owner_id":"bob","contract_id":"community","amount":"100"}"#),
+            log("community_renounced", r#"{"owner_id":"bob","contract_id":"community","blocked":false}"#),
+        ];
+        let ledger = Ledger::replay(&logs).unwrap();
+        assert_eq!(ledger.balance("bob", "community"), None);
+        assert_eq!(ledger.supply["community"], Supply { available: 0, total: 0 });
+    }
+
+    #[test]
+    fn replay_account_merged() {
+        let logs = vec![
+            log("ft_mint", r#"{"owner_id":"bob","contract_id":"community","amount":"100"},{"owner_id":"carol","contract_id":"community","amount":"50"}"#),
+            log("account_unregistered", r#"{"account_id":"bob"}"#),
+            log("account_merged", r#"{"account_id":"bob","new_account_id":"carol"}"#),
+        ];
+        let balance = Ledger::replay(&logs).unwrap().balance("carol", "community").cloned().unwrap();
+        assert_eq!((balance.available, balance.total), (150, 150));
+    }
+
+    #[test]
+    fn replay_not_enough_balance() {
+        let logs = vec![
+            log("ft_mint", r#"{"owner_id":"bob","contract_id":"community","amount":"10"}"#),
+            log("ft_burn", r#"{"owner_id":"bob","contract_id":"community","amount":"20"}"#),
+        ];
+        assert!(matches!(Ledger::replay(&logs), Err(ReplayError::NotEnoughBalance { event: "ft_burn", amount: 20, .. })));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod events;
+pub mod ledger;
+
+pub use events::EventLog;
+pub use ledger::{Ledger, ReplayError};
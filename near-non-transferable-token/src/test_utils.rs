@@ -0,0 +1,31 @@
+//! Fixtures shared by the unit tests.
+
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{testing_env, AccountId};
+
+use crate::fungible_token::FungibleToken;
+
+/// The community account most tests mint and deposit from.
+pub(crate) fn community() -> AccountId {
+    AccountId::new_unchecked("community".to_string())
+}
+
+pub(crate) fn set_caller(predecessor_account_id: AccountId, attached_deposit: u128) {
+    set_caller_at(predecessor_account_id, attached_deposit, 0);
+}
+
+pub(crate) fn set_caller_at(predecessor_account_id: AccountId, attached_deposit: u128, block_timestamp: u64) {
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(predecessor_account_id)
+        .attached_deposit(attached_deposit)
+        .block_timestamp(block_timestamp)
+        .build());
+}
+
+/// A token with `accounts(1)` registered, called by `predecessor_account_id`.
+pub(crate) fn registered_token(predecessor_account_id: AccountId, attached_deposit: u128) -> FungibleToken {
+    set_caller(predecessor_account_id, attached_deposit);
+    let mut token = FungibleToken::new(b"t".to_vec());
+    token.internal_register_account(&accounts(1));
+    token
+}
@@ -0,0 +1,175 @@
+//! A contract built from every macro of `near-non-transferable-token`, as a starting point and
+//! to keep the macros compiling against the library.
+
+use near_non_transferable_token::access_control::core::AccessControlCore;
+use near_non_transferable_token::access_control::timelock::{AdminAction, AdminProposal, AdminTimelockCore, TimelockConfig};
+use near_non_transferable_token::access_control::{AccessControl, AdminTimelock, Role};
+use near_non_transferable_token::fungible_token::clawback::FungibleTokenClawback;
+use near_non_transferable_token::fungible_token::compat::FungibleTokenTransferCompat;
+use near_non_transferable_token::fungible_token::consent::{ConsentPolicy, FungibleTokenConsent, PendingMint};
+use near_non_transferable_token::fungible_token::core::FungibleTokenCore;
+use near_non_transferable_token::fungible_token::deposit_registry::{CommunityDeposit, Depositor, FungibleTokenDepositRegistry};
+use near_non_transferable_token::fungible_token::merge::FungibleTokenMerge;
+use near_non_transferable_token::fungible_token::metadata::{
+    FungibleTokenCommunityMetadataProvider, FungibleTokenMetadata, FungibleTokenMetadataHolder, FungibleTokenMetadataProvider,
+    FungibleTokenMetadataUpdate,
+};
+use near_non_transferable_token::fungible_token::multi_token::{MtBaseTokenMetadata, MultiTokenView, TokenId};
+use near_non_transferable_token::fungible_token::pause::{FungibleTokenPause, Pause};
+use near_non_transferable_token::fungible_token::recovery::{FungibleTokenRecovery, Recovery, RecoveryGuardians};
+use near_non_transferable_token::fungible_token::renounce::FungibleTokenRenounce;
+use near_non_transferable_token::fungible_token::resolver::FungibleTokenResolver;
+use near_non_transferable_token::fungible_token::FungibleToken;
+use near_non_transferable_token::non_fungible_token::core::{SoulboundTokenCore, SoulboundTokenIssuers};
+use near_non_transferable_token::non_fungible_token::enumeration::SoulboundTokenEnumeration;
+use near_non_transferable_token::non_fungible_token::metadata::{Token, TokenMetadata};
+use near_non_transferable_token::non_fungible_token::sbt::{SbtOwnedToken, SbtToken, SbtTokenId, SbtTokenMetadata, SoulboundTokenRegistry};
+use near_non_transferable_token::non_fungible_token::SoulboundToken;
+use near_non_transferable_token::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
+use near_non_transferable_token::{
+    impl_access_control, impl_admin_timelock, impl_fungible_token_clawback, impl_fungible_token_community_metadata,
+    impl_fungible_token_consent, impl_fungible_token_core, impl_fungible_token_deposit_registry, impl_fungible_token_merge,
+    impl_fungible_token_metadata, impl_fungible_token_multi_token_view, impl_fungible_token_nep141_compat,
+    impl_fungible_token_pause, impl_fungible_token_recovery, impl_fungible_token_renounce, impl_fungible_token_storage,
+    impl_soulbound_token, impl_soulbound_token_registry, require_role,
+};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near_bindgen, AccountId, PanicOnDefault, PromiseOrValue};
+
+/// One day, in nanoseconds.
+const ADMIN_DELAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    token: FungibleToken,
+    owner_id: AccountId,
+    metadata: FungibleTokenMetadataHolder,
+    badges: SoulboundToken,
+    acl: AccessControl,
+    timelock: AdminTimelock,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, metadata: FungibleTokenMetadata) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        let mut acl = AccessControl::new(b"r".to_vec(), &owner_id);
+        Self {
+            token: FungibleToken::new(b"a".to_vec()),
+            metadata: FungibleTokenMetadataHolder::new(b"m".to_vec(), &metadata),
+            badges: SoulboundToken::new(b"b".to_vec()),
+            timelock: AdminTimelock::new(b"q".to_vec(), &mut acl, 1, ADMIN_DELAY),
+            acl,
+            owner_id,
+        }
+    }
+
+    /// Mints `amount` of the tokens of `contract_id` to `owner_id`, by a minter of the community.
+    /// Returns whether the tokens were credited rather than left pending.
+    pub fn ft_mint(&mut self, owner_id: AccountId, contract_id: AccountId, amount: U128, memo: Option<String>) -> bool {
+        require_role!(self.acl, Role::Minter, &contract_id);
+        self.token.internal_mint(&owner_id, amount.0, &contract_id, memo.as_deref())
+    }
+}
+
+impl_fungible_token_core!(Contract, token);
+impl_fungible_token_storage!(Contract, token);
+impl_fungible_token_deposit_registry!(Contract, token);
+impl_fungible_token_community_metadata!(Contract, token);
+impl_fungible_token_metadata!(Contract, metadata, owner_id);
+impl_fungible_token_nep141_compat!(Contract, token);
+impl_fungible_token_multi_token_view!(Contract, token);
+impl_fungible_token_recovery!(Contract, token, acl);
+impl_fungible_token_merge!(Contract, token);
+impl_fungible_token_clawback!(Contract, token, acl);
+impl_fungible_token_consent!(Contract, token);
+impl_fungible_token_renounce!(Contract, token);
+impl_fungible_token_pause!(Contract, token, acl);
+impl_access_control!(Contract, acl);
+impl_admin_timelock!(Contract, timelock, acl, token);
+impl_soulbound_token!(Contract, badges);
+impl_soulbound_token_registry!(Contract, badges);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, ONE_NEAR};
+
+    fn set_caller(predecessor_account_id: AccountId, attached_deposit: u128) {
+        set_caller_at(predecessor_account_id, attached_deposit, 0);
+    }
+
+    fn set_caller_at(predecessor_account_id: AccountId, attached_deposit: u128, block_timestamp: u64) {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(predecessor_account_id)
+            .attached_deposit(attached_deposit)
+            .block_timestamp(block_timestamp)
+            .build());
+    }
+
+    fn setup() -> Contract {
+        set_caller(accounts(0), ONE_NEAR);
+        let metadata = FungibleTokenMetadata {
+            spec: "ft-1.0.0".to_string(),
+            name: "Example".to_string(),
+            symbol: "EX".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 0,
+        };
+        let mut contract = Contract::new(accounts(0), metadata);
+        set_caller(accounts(1), ONE_NEAR);
+        contract.storage_deposit(None, None);
+        contract
+    }
+
+    #[test]
+    fn mint_by_community() {
+        let mut contract = setup();
+        set_caller(accounts(2), 0);
+        assert!(contract.ft_mint(accounts(1), accounts(2), U128(100), None));
+        assert_eq!(contract.ft_balance_of(accounts(1), Some(accounts(2))).0, 100);
+        assert_eq!(contract.ft_balance_of(accounts(1), None).0, 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "The account is missing the minter role")]
+    fn mint_by_stranger() {
+        let mut contract = setup();
+        set_caller(accounts(3), 0);
+        contract.ft_mint(accounts(1), accounts(2), U128(100), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minting is paused for this community")]
+    fn mint_while_paused() {
+        let mut contract = setup();
+        set_caller(accounts(2), 1);
+        contract.ft_set_community_pause(accounts(2), Pause { mint: true, ..Default::default() });
+        contract.ft_mint(accounts(1), accounts(2), U128(100), None);
+    }
+
+    #[test]
+    fn pause_through_timelock() {
+        let mut contract = setup();
+        let pause = Pause { mint: true, ..Default::default() };
+        set_caller(accounts(0), ONE_NEAR);
+        let id = contract.admin_propose(AdminAction::SetPause { pause });
+        set_caller_at(accounts(3), 1, ADMIN_DELAY);
+        contract.admin_execute(id);
+        assert_eq!(contract.ft_pause_status(None, None), pause);
+    }
+
+    #[test]
+    #[should_panic(expected = "The change has to go through the admin timelock")]
+    fn direct_grant_by_owner() {
+        let mut contract = setup();
+        set_caller(accounts(0), ONE_NEAR);
+        contract.acl_grant_role(Role::Pauser, accounts(0), None);
+    }
+}
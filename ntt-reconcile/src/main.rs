@@ -0,0 +1,63 @@
+//! Rebuilds the balances of a non-transferable token from its exported logs and compares them
+//! with a state dump from view calls.
+//!
+//! Usage: `ntt-reconcile <logs.jsonl> <state.json>`
+//!
+//! Each line of the logs file is a JSON string holding one log, or a receipt object with a
+//! `logs` array, in execution order. The state file is a JSON array of
+//! `{"account_id", "contract_id", "available", "total", "deposits"}`, with `deposits` an optional
+//! map from the receiving contract to the amount. Exits with status 1 if anything differs.
+
+mod reconcile;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::process;
+
+use near_non_transferable_token::replay::Ledger;
+
+use crate::reconcile::{reconcile, LogLine, StateEntry};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 {
+        eprintln!("Usage: {} <logs.jsonl> <state.json>", args.first().map_or("ntt-reconcile", String::as_str));
+        process::exit(2);
+    }
+    match run(&args[1], &args[2]) {
+        Ok(0) => println!("No mismatches"),
+        Ok(count) => {
+            println!("{} mismatches", count);
+            process::exit(1);
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(2);
+        }
+    }
+}
+
+fn run(logs_path: &str, state_path: &str) -> Result<usize, String> {
+    let logs = File::open(logs_path).map_err(|e| format!("Cannot open {}: {}", logs_path, e))?;
+    let mut ledger = Ledger::default();
+    for (index, line) in BufReader::new(logs).lines().enumerate() {
+        let line = line.map_err(|e| format!("Cannot read {}: {}", logs_path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line: LogLine = serde_json::from_str(&line).map_err(|e| format!("{}:{}: {}", logs_path, index + 1, e))?;
+        for log in line.into_logs() {
+            ledger.apply_log(&log).map_err(|e| format!("{}:{}: {}", logs_path, index + 1, e))?;
+        }
+    }
+
+    let state = File::open(state_path).map_err(|e| format!("Cannot open {}: {}", state_path, e))?;
+    let state: Vec<StateEntry> =
+        serde_json::from_reader(BufReader::new(state)).map_err(|e| format!("{}: {}", state_path, e))?;
+
+    let mismatches = reconcile(&ledger, &state);
+    for mismatch in &mismatches {
+        println!("{}", mismatch);
+    }
+    Ok(mismatches.len())
+}
@@ -0,0 +1,178 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use near_non_transferable_token::replay::ledger::Balance;
+use near_non_transferable_token::replay::Ledger;
+use serde::{de, Deserialize, Deserializer};
+
+/// One line of the exported logs: either a single log, or a receipt with its logs.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum LogLine {
+    Log(String),
+    Receipt { logs: Vec<String> },
+}
+
+impl LogLine {
+    pub fn into_logs(self) -> Vec<String> {
+        match self {
+            Self::Log(log) => vec![log],
+            Self::Receipt { logs } => logs,
+        }
+    }
+}
+
+/// The balance of `account_id` in `contract_id` as reported by the view calls:
+/// `ft_balance_of`, `ft_total_balance_of` and, if dumped, `ft_deposit_of` per receiver.
+#[derive(Deserialize)]
+pub struct StateEntry {
+    pub account_id: String,
+    pub contract_id: String,
+    #[serde(deserialize_with = "u128_dec")]
+    pub available: u128,
+    #[serde(deserialize_with = "u128_dec")]
+    pub total: u128,
+    #[serde(default, deserialize_with = "u128_dec_map")]
+    pub deposits: Option<BTreeMap<String, u128>>,
+}
+
+fn u128_dec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+    String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+}
+
+fn u128_dec_map<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<BTreeMap<String, u128>>, D::Error> {
+    let deposits: Option<BTreeMap<String, String>> = Option::deserialize(deserializer)?;
+    deposits
+        .map(|deposits| {
+            deposits
+                .into_iter()
+                .map(|(deposit_contract_id, amount)| amount.parse().map(|amount| (deposit_contract_id, amount)))
+                .collect::<Result<_, _>>()
+        })
+        .transpose()
+        .map_err(de::Error::custom)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub account_id: String,
+    pub contract_id: String,
+    pub field: String,
+    pub replayed: Option<u128>,
+    pub state: Option<u128>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |amount: Option<u128>| amount.map_or_else(|| "missing".to_string(), |amount| amount.to_string());
+        write!(
+            f,
+            "{} {} {}: replayed {}, state {}",
+            self.account_id,
+            self.contract_id,
+            self.field,
+            show(self.replayed),
+            show(self.state)
+        )
+    }
+}
+
+/// Compares the replayed balances with the state dump. Balances missing from the dump are
+/// reported too, as a dump is expected to cover every account and community in the logs.
+pub fn reconcile(ledger: &Ledger, state: &[StateEntry]) -> Vec<Mismatch> {
+    let mut mismatches = vec![];
+    let mut seen = BTreeSet::new();
+    for entry in state {
+        seen.insert((entry.account_id.as_str(), entry.contract_id.as_str()));
+        let empty = Balance::default();
+        let balance = ledger.balance(&entry.account_id, &entry.contract_id).unwrap_or(&empty);
+        let mut check = |field: String, replayed: u128, state: u128| {
+            if replayed != state {
+                mismatches.push(Mismatch {
+                    account_id: entry.account_id.clone(),
+                    contract_id: entry.contract_id.clone(),
+                    field,
+                    replayed: Some(replayed),
+                    state: Some(state),
+                });
+            }
+        };
+        check("available".to_string(), balance.available, entry.available);
+        check("total".to_string(), balance.total, entry.total);
+        if let Some(deposits) = &entry.deposits {
+            let deposit_contract_ids: BTreeSet<&String> = deposits.keys().chain(balance.deposits.keys()).collect();
+            for deposit_contract_id in deposit_contract_ids {
+                check(
+                    format!("deposit {}", deposit_contract_id),
+                    balance.deposits.get(deposit_contract_id).copied().unwrap_or(0),
+                    deposits.get(deposit_contract_id).copied().unwrap_or(0),
+                );
+            }
+        }
+    }
+    for (account_id, balances) in &ledger.accounts {
+        for (contract_id, balance) in balances {
+            if !seen.contains(&(account_id.as_str(), contract_id.as_str())) {
+                mismatches.push(Mismatch {
+                    account_id: account_id.clone(),
+                    contract_id: contract_id.clone(),
+                    field: "total".to_string(),
+                    replayed: Some(balance.total),
+                    state: None,
+                });
+            }
+        }
+    }
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger() -> Ledger {
+        Ledger::replay([
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_mint","data":[{"owner_id":"bob","contract_id":"community","amount":"100"},{"owner_id":"alice","contract_id":"community","amount":"50"}]}"#,
+            r#"EVENT_JSON:{"standard":"ntt","version":"2.0.0","event":"ft_deposit","data":[{"owner_id":"bob","contract_id":"community","deposit_contract_id":"danny","amount":"40"}]}"#,
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_log_lines() {
+        let line: LogLine = serde_json::from_str(r#"{"receipt_id":"abc","logs":["one","two"]}"#).unwrap();
+        assert_eq!(line.into_logs(), vec!["one", "two"]);
+        let line: LogLine = serde_json::from_str(r#""one""#).unwrap();
+        assert_eq!(line.into_logs(), vec!["one"]);
+    }
+
+    #[test]
+    fn reconcile_mismatches() {
+        let state: Vec<StateEntry> = serde_json::from_str(
+            r#"[
+                {"account_id":"bob","contract_id":"community","available":"60","total":"100","deposits":{"danny":"30"}},
+                {"account_id":"carol","contract_id":"community","available":"0","total":"0"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            reconcile(&ledger(), &state),
+            vec![
+                Mismatch {
+                    account_id: "bob".to_string(),
+                    contract_id: "community".to_string(),
+                    field: "deposit danny".to_string(),
+                    replayed: Some(40),
+                    state: Some(30),
+                },
+                Mismatch {
+                    account_id: "alice".to_string(),
+                    contract_id: "community".to_string(),
+                    field: "total".to_string(),
+                    replayed: Some(50),
+                    state: None,
+                },
+            ]
+        );
+    }
+}